@@ -0,0 +1,84 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Chat Administrator Cache
+//  Per-chat admin list with a TTL, so permission checks, /admins and
+//  /userinfo don't call get_chat_administrators on every message.
+//  Invalidated by chat_member / my_chat_member updates and /admincache.
+// ════════════════════════════════════════════════════════════════
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tgbotrs::Bot;
+
+use crate::permissions::MemberInfo;
+
+struct Entry {
+    fetched_at: Instant,
+    admins: Vec<MemberInfo>,
+}
+
+#[derive(Clone)]
+pub struct AdminCache {
+    ttl: Duration,
+    inner: Arc<Mutex<HashMap<i64, Entry>>>,
+}
+
+impl AdminCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        AdminCache {
+            ttl: Duration::from_secs(ttl_secs),
+            inner: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Cached admin list, fetched from Telegram when missing or expired.
+    pub async fn admins(&self, bot: &Bot, chat_id: i64) -> Result<Vec<MemberInfo>, tgbotrs::BotError> {
+        {
+            let map = self.inner.lock().unwrap();
+            if let Some(e) = map.get(&chat_id) {
+                if e.fetched_at.elapsed() < self.ttl {
+                    return Ok(e.admins.clone());
+                }
+            }
+        }
+        self.refresh(bot, chat_id).await
+    }
+
+    /// Always fetches a fresh list and replaces the cached one.
+    pub async fn refresh(&self, bot: &Bot, chat_id: i64) -> Result<Vec<MemberInfo>, tgbotrs::BotError> {
+        let raw: Vec<Value> = bot
+            .call_api("getChatAdministrators", &json!({ "chat_id": chat_id }))
+            .await?;
+        let admins: Vec<MemberInfo> = raw.into_iter().map(MemberInfo::from_value).collect();
+        log::debug!("admin cache refreshed for {} ({} admins)", chat_id, admins.len());
+        self.inner.lock().unwrap().insert(chat_id, Entry {
+            fetched_at: Instant::now(),
+            admins: admins.clone(),
+        });
+        Ok(admins)
+    }
+
+    /// Status of one user, answered from the admin list. Anyone not on the
+    /// list is reported as a plain member — callers that need the exact
+    /// non-admin status (restricted, left, kicked) should ask Telegram.
+    pub async fn lookup(&self, bot: &Bot, chat_id: i64, user_id: i64) -> Result<MemberInfo, tgbotrs::BotError> {
+        let admins = self.admins(bot, chat_id).await?;
+        Ok(admins
+            .into_iter()
+            .find(|m| m.user_id() == user_id)
+            .unwrap_or_else(|| MemberInfo::from_value(json!({ "status": "member" }))))
+    }
+
+    /// Seconds since the chat's list was fetched, if cached.
+    pub fn age(&self, chat_id: i64) -> Option<u64> {
+        self.inner.lock().unwrap().get(&chat_id).map(|e| e.fetched_at.elapsed().as_secs())
+    }
+
+    pub fn invalidate(&self, chat_id: i64) {
+        if self.inner.lock().unwrap().remove(&chat_id).is_some() {
+            log::debug!("admin cache invalidated for {}", chat_id);
+        }
+    }
+}
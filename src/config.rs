@@ -10,6 +10,8 @@ pub struct Config {
     pub webhook: WebhookConfig,
     pub admin_id: Option<i64>,
     pub api_url: Option<String>,
+    pub admin_cache_ttl: u64,
+    pub storage: StorageConfig,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -18,6 +20,18 @@ pub enum BotMode {
     Webhook,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageBackend {
+    Memory,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    pub path: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct PollingConfig {
     pub timeout: i64,
@@ -80,6 +94,22 @@ impl Config {
 
         let admin_id = env::var("ADMIN_ID").ok().and_then(|s| s.parse::<i64>().ok());
         let api_url = env::var("TELEGRAM_API_URL").ok();
+        let admin_cache_ttl = env::var("ADMIN_CACHE_TTL")
+            .unwrap_or_else(|_| "600".into())
+            .parse()
+            .unwrap_or(600);
+
+        let storage = StorageConfig {
+            backend: match env::var("STORAGE_BACKEND")
+                .unwrap_or_else(|_| "json".to_string())
+                .to_lowercase()
+                .as_str()
+            {
+                "memory" => StorageBackend::Memory,
+                _ => StorageBackend::Json,
+            },
+            path: env::var("STORAGE_PATH").unwrap_or_else(|_| "data/rustace.jsonl".into()),
+        };
 
         Ok(Config {
             bot_token,
@@ -88,6 +118,8 @@ impl Config {
             webhook,
             admin_id,
             api_url,
+            admin_cache_ttl,
+            storage,
         })
     }
 }
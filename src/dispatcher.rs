@@ -10,19 +10,46 @@ use tgbotrs::{
 
 use crate::handlers::{
     admin,
+    anon_admin,
+    antiflood,
+    approvals,
+    antiraid,
+    antispam,
+    audit,
+    blocklist,
     broadcast,
     callbacks::handle_callback,
+    captcha,
     commands::*,
-    filters::{self, FilterStore},
+    filters,
+    gbans,
+    greetings,
     inline::handle_inline_query,
-    moderation::{self, WarnStore},
-    notes::{self, NoteStore},
+    join_requests,
+    locks,
+    moderation,
+    notes,
+    reports,
+    system,
+    warns,
 };
-
+use crate::admin_cache::AdminCache;
+use crate::flood::FloodTracker;
+use crate::html;
+use crate::permissions;
+use crate::registry::{self, Access, CommandSpec};
+use crate::roles::{self, Roles};
+use crate::scheduler::Scheduler;
+use crate::storage::{Db, CHATS};
+use crate::targets;
+
+#[derive(Clone)]
 pub struct Stores {
-    pub warn:   WarnStore,
-    pub filter: FilterStore,
-    pub note:   NoteStore,
+    pub db:        Db,
+    pub admins:    AdminCache,
+    pub roles:     Roles,
+    pub scheduler: Scheduler,
+    pub flood:     FloodTracker,
 }
 
 pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores) {
@@ -35,18 +62,54 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
         let first_name = msg.from.as_ref().map(|u| u.first_name.as_str()).unwrap_or("there");
         let msg_id     = msg.message_id;
 
-        // Reply-to info (used by moderation + admin commands)
-        let reply_user_id:   Option<i64>    = msg.reply_to_message.as_ref().and_then(|r| r.from.as_ref()).map(|u| u.id);
-        let reply_user_name: Option<String> = msg.reply_to_message.as_ref().and_then(|r| r.from.as_ref()).map(|u| u.first_name.clone());
+        // Keep the user directory fresh for @username targets
+        for user in msg.from.iter().chain(msg.reply_to_message.as_ref().and_then(|r| r.from.as_ref())) {
+            targets::remember(&stores.db, user);
+        }
+        // New members: global bans and anti-raid first, then captcha for those let in
+        let mut admitted = Vec::new();
+        for user in msg.new_chat_members.iter().flatten() {
+            targets::remember(&stores.db, user);
+            if gbans::check(&bot, chat_id, user.id, &stores.db).await { continue; }
+            if antiraid::on_join(&bot, chat_id, user, &stores.db, &stores.flood, &stores.scheduler, &stores.admins).await { continue; }
+            captcha::on_join(&bot, chat_id, user, &stores.db, &stores.scheduler).await;
+            antispam::on_join(&stores.db, chat_id, user);
+            admitted.push(user.clone());
+        }
+        let chat_title = msg.chat.title.as_deref().unwrap_or("this chat");
+        if msg.new_chat_members.is_some() {
+            greetings::on_join(&bot, chat_id, chat_title, &admitted, msg_id, &stores.db).await;
+        }
+        if let Some(user) = &msg.left_chat_member {
+            antispam::on_leave(&stores.db, chat_id, user.id);
+            greetings::on_leave(&bot, chat_id, chat_title, user, msg_id, &stores.db).await;
+        }
+        // …and the chat title for audit-log entries
+        if msg.chat.r#type == "group" || msg.chat.r#type == "supergroup" {
+            remember_chat(&stores.db, chat_id, msg.chat.title.as_deref());
+        }
+
         let reply_msg_id:    Option<i64>    = msg.reply_to_message.as_ref().map(|r| r.message_id);
         let chat_type       = msg.chat.r#type.as_str().to_owned(); // "private" | "group" | "supergroup" | "channel"
         let is_private      = chat_type == "private";
         let msg_date        = msg.date;
 
+        // Globally banned senders (joins were handled above)
+        if !is_private && msg.new_chat_members.is_none() && user_id != 0
+            && gbans::check(&bot, chat_id, user_id, &stores.db).await { return; }
+        // Deletion-based content locks
+        if !is_private && locks::check(&bot, &msg, &stores.db, &stores.admins).await { return; }
+        // Blocklisted words (text or caption)
+        if !is_private && blocklist::check(&bot, &msg, &stores.db, &stores.scheduler, &stores.admins).await { return; }
+        // Too many messages too fast
+        if !is_private && antiflood::check(&bot, &msg, &stores.db, &stores.flood, &stores.scheduler, &stores.admins).await { return; }
+        // New members' first messages that score as spam
+        if !is_private && antispam::check(&bot, &msg, &stores.db, &stores.scheduler, &stores.admins).await { return; }
+
         if let Some(ref text) = msg.text {
             // ── Filters & Notes auto-triggers (before command parsing) ────────
             // #notename shortcut
-            if notes::check_hashtag_note(&bot, chat_id, text, &stores.note).await { return; }
+            if notes::check_hashtag_note(&bot, chat_id, text, &stores.db).await { return; }
 
             let mut parts   = text.split_whitespace();
             let command_raw = parts.next().unwrap_or("");
@@ -59,113 +122,183 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
             // Text after command as full string (for /send, /post, /note etc)
             let rest_of_line: &str = text[command_raw.len()..].trim();
 
-            match command {
+            // ── Registry lookup + access gate ─────────────────────────────
+            let spec = command.strip_prefix('/').and_then(registry::find);
+            if let Some(spec) = spec {
+                // Anonymous admins: a real admin confirms before it runs
+                if anon_admin::is_anonymous(&msg) && matches!(spec.access, Access::Chat(_)) {
+                    anon_admin::hold(&bot, &msg, spec, &stores.db).await;
+                    return;
+                }
+                if !authorize(&bot, &stores, spec, chat_id, user_id, is_private).await { return; }
+            }
+
+            // Target for moderation / admin commands: reply, ID, @user or mention
+            let target = targets::resolve(&msg, &stores.db);
+
+            match spec.map(|s| s.name) {
                 // ── Core ──────────────────────────────────────────────────
-                "/start" | "/menu" => handle_start(&bot, chat_id, first_name).await,
-                "/help"            => handle_help(&bot, chat_id).await,
-                "/about"           => handle_about(&bot, chat_id, None).await,
-                "/library"         => handle_library(&bot, chat_id, None).await,
-                "/textstyles"      => handle_text_styles(&bot, chat_id, None).await,
-                "/stats"           => handle_stats(&bot, chat_id).await,
+                Some("start")        => handle_start(&bot, chat_id, first_name).await,
+                Some("help")         => handle_help(&bot, chat_id).await,
+                Some("about")        => handle_about(&bot, chat_id, None).await,
+                Some("library")      => handle_library(&bot, chat_id, None).await,
+                Some("textstyles")   => handle_text_styles(&bot, chat_id, None).await,
+                Some("stats")        => handle_stats(&bot, chat_id).await,
 
                 // ── Fun ───────────────────────────────────────────────────
-                "/dice"       => handle_dice(&bot, chat_id, "🎲").await,
-                "/darts"      => handle_dice(&bot, chat_id, "🎯").await,
-                "/bowling"    => handle_dice(&bot, chat_id, "🎳").await,
-                "/basketball" => handle_dice(&bot, chat_id, "🏀").await,
-                "/football"   => handle_dice(&bot, chat_id, "⚽").await,
-                "/slots"      => handle_dice(&bot, chat_id, "🎰").await,
-                "/fact"       => handle_fact(&bot, chat_id).await,
-                "/joke"       => handle_joke(&bot, chat_id).await,
-                "/magic8"     => handle_magic8(&bot, chat_id).await,
-                "/coinflip"   => handle_coinflip(&bot, chat_id).await,
+                Some("dice")       => handle_dice(&bot, chat_id, "🎲").await,
+                Some("darts")      => handle_dice(&bot, chat_id, "🎯").await,
+                Some("bowling")    => handle_dice(&bot, chat_id, "🎳").await,
+                Some("basketball") => handle_dice(&bot, chat_id, "🏀").await,
+                Some("football")   => handle_dice(&bot, chat_id, "⚽").await,
+                Some("slots")      => handle_dice(&bot, chat_id, "🎰").await,
+                Some("fact")       => handle_fact(&bot, chat_id).await,
+                Some("joke")       => handle_joke(&bot, chat_id).await,
+                Some("magic8")     => handle_magic8(&bot, chat_id).await,
+                Some("coinflip")   => handle_coinflip(&bot, chat_id).await,
 
                 // ── Media demos ───────────────────────────────────────────
-                "/photo"      => handle_photo(&bot, chat_id).await,
-                "/animation"  => handle_animation(&bot, chat_id).await,
-                "/location"   => handle_location(&bot, chat_id).await,
-                "/venue"      => handle_venue(&bot, chat_id).await,
-                "/contact"    => handle_contact(&bot, chat_id).await,
-                "/poll"       => handle_poll(&bot, chat_id).await,
+                Some("photo")      => handle_photo(&bot, chat_id).await,
+                Some("animation")  => handle_animation(&bot, chat_id).await,
+                Some("location")   => handle_location(&bot, chat_id).await,
+                Some("venue")      => handle_venue(&bot, chat_id).await,
+                Some("contact")    => handle_contact(&bot, chat_id).await,
+                Some("poll")       => handle_poll(&bot, chat_id).await,
 
                 // ── Info ──────────────────────────────────────────────────
-                "/botinfo"     => handle_bot_info(&bot, chat_id, None).await,
-                "/webhookinfo" => handle_webhook_info(&bot, chat_id, None).await,
-                "/membercount" => handle_member_count(&bot, chat_id, None).await,
-                "/admins"      => handle_admins(&bot, chat_id, None).await,
-                "/invitelink"  => handle_invite_link(&bot, chat_id, None).await,
-                "/mycommands"  => handle_my_commands(&bot, chat_id, None).await,
-                "/myprofile"   => handle_my_profile(&bot, chat_id, user_id).await,
+                Some("botinfo")     => handle_bot_info(&bot, chat_id, None).await,
+                Some("webhookinfo") => handle_webhook_info(&bot, chat_id, None).await,
+                Some("membercount") => handle_member_count(&bot, chat_id, None).await,
+                Some("admins")      => handle_admins(&bot, chat_id, None, &stores.admins).await,
+                Some("invitelink")  => handle_invite_link(&bot, chat_id, None).await,
+                Some("mycommands")  => handle_my_commands(&bot, chat_id, None).await,
+                Some("myprofile")   => handle_my_profile(&bot, chat_id, user_id).await,
 
                 // ── Admin commands ────────────────────────────────────────
-                "/promote"  => admin::handle_promote(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), args).await,
-                "/demote"   => admin::handle_demote(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), args).await,
-                "/title"    => admin::handle_title(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), args).await,
-                "/userinfo" | "/whois" => admin::handle_userinfo(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), arg0).await,
+                Some("promote")    => admin::handle_promote(&bot, chat_id, user_id, &target, &stores.db, &stores.admins).await,
+                Some("demote")     => admin::handle_demote(&bot, chat_id, user_id, &target, &stores.db, &stores.admins).await,
+                Some("title")      => admin::handle_title(&bot, chat_id, user_id, &target, &stores.db, &stores.admins).await,
+                Some("userinfo")   => admin::handle_userinfo(&bot, chat_id, &target, &stores.admins).await,
+                Some("admincache") => admin::handle_admincache(&bot, chat_id, user_id, &stores.admins).await,
+                Some("modlog")     => audit::handle_modlog(&bot, chat_id, &target, &stores.db).await,
+                Some("setlog")     => audit::handle_set_log(&bot, chat_id, user_id, arg0, &stores.db).await,
 
                 // ── Moderation ────────────────────────────────────────────
-                "/modhelp" => moderation::handle_mod_help(&bot, chat_id).await,
-                "/ban"     => moderation::handle_ban(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), arg0).await,
-                "/unban"   => moderation::handle_unban(&bot, chat_id, reply_user_id, reply_user_name.as_deref()).await,
-                "/kick"    => moderation::handle_kick(&bot, chat_id, reply_user_id, reply_user_name.as_deref()).await,
-                "/mute"    => moderation::handle_mute(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), arg0).await,
-                "/unmute"  => moderation::handle_unmute(&bot, chat_id, reply_user_id, reply_user_name.as_deref()).await,
-                "/warn"    => moderation::handle_warn(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), &stores.warn).await,
-                "/unwarn"  => moderation::handle_unwarn(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), &stores.warn).await,
-                "/warns"   => moderation::handle_warns(&bot, chat_id, reply_user_id, reply_user_name.as_deref(), &stores.warn).await,
-                "/delete" | "/del" => moderation::handle_delete(&bot, chat_id, reply_msg_id, msg_id).await,
-                "/pin"     => moderation::handle_pin(&bot, chat_id, reply_msg_id).await,
-                "/unpin"   => moderation::handle_unpin(&bot, chat_id).await,
-                "/ro"      => moderation::handle_ro(&bot, chat_id).await,
-                "/unro"    => moderation::handle_unro(&bot, chat_id).await,
+                Some("modhelp") => moderation::handle_mod_help(&bot, chat_id).await,
+                Some("ban")     => moderation::handle_ban(&bot, chat_id, user_id, &target, &stores.db, &stores.scheduler, &stores.admins).await,
+                Some("unban")   => moderation::handle_unban(&bot, chat_id, user_id, &target, &stores.db, &stores.scheduler).await,
+                Some("kick")    => moderation::handle_kick(&bot, chat_id, user_id, &target, &stores.db, &stores.admins).await,
+                Some("mute")    => moderation::handle_mute(&bot, chat_id, user_id, &target, &stores.db, &stores.scheduler, &stores.admins).await,
+                Some("restrict") => moderation::handle_restrict(&bot, chat_id, user_id, &target, &stores.db, &stores.scheduler, &stores.admins).await,
+                Some("unmute")  => moderation::handle_unmute(&bot, chat_id, user_id, &target, &stores.db, &stores.scheduler).await,
+                Some("delete")  => moderation::handle_delete(&bot, chat_id, user_id, reply_msg_id, msg_id, &target, &stores.db).await,
+                Some("purge")   => moderation::handle_purge(&bot, chat_id, user_id, msg.reply_to_message.as_deref(), msg_id, arg0, false, &stores.db).await,
+                Some("spurge")  => moderation::handle_purge(&bot, chat_id, user_id, msg.reply_to_message.as_deref(), msg_id, arg0, true, &stores.db).await,
+                Some("pin")     => moderation::handle_pin(&bot, chat_id, user_id, reply_msg_id, arg0, &stores.db, &stores.scheduler).await,
+                Some("unpin")   => moderation::handle_unpin(&bot, chat_id, user_id, &stores.db).await,
+                Some("ro")      => moderation::handle_ro(&bot, chat_id, user_id, arg0, &stores.db, &stores.scheduler).await,
+                Some("unro")    => moderation::handle_unro(&bot, chat_id, user_id, &stores.db, &stores.scheduler).await,
+                Some("pending") => moderation::handle_pending(&bot, chat_id, &stores.scheduler).await,
+
+                // ── Locks ─────────────────────────────────────────────────
+                Some("lock")   => locks::handle_lock(&bot, chat_id, args, &stores.db).await,
+                Some("unlock") => locks::handle_unlock(&bot, chat_id, args, &stores.db).await,
+                Some("locks")  => locks::handle_locks(&bot, chat_id, &stores.db).await,
+
+                // ── Blocklist ─────────────────────────────────────────────
+                Some("blocklist") => blocklist::handle_list(&bot, chat_id, &stores.db).await,
+                Some("addblock")  => blocklist::handle_add(&bot, chat_id, rest_of_line, &stores.db).await,
+                Some("rmblock")   => blocklist::handle_remove(&bot, chat_id, rest_of_line, &stores.db).await,
+                Some("blockmode") => blocklist::handle_mode(&bot, chat_id, args, &stores.db).await,
+
+                // ── Antiflood ─────────────────────────────────────────────
+                Some("flood")     => antiflood::handle_flood(&bot, chat_id, &stores.db).await,
+                Some("setflood")  => antiflood::handle_set_flood(&bot, chat_id, args, &stores.db, &stores.flood).await,
+                Some("floodmode") => antiflood::handle_flood_mode(&bot, chat_id, args, &stores.db).await,
+
+                // ── Anti-raid ─────────────────────────────────────────────
+                Some("antiraid") => antiraid::handle_antiraid(&bot, chat_id, user_id, arg0, &stores.db, &stores.scheduler, &stores.admins).await,
+                Some("raidmode") => antiraid::handle_raid_mode(&bot, chat_id, arg0, &stores.db).await,
+                Some("autoraid") => antiraid::handle_auto_raid(&bot, chat_id, args, &stores.db).await,
+
+                // ── Antispam ──────────────────────────────────────────────
+                Some("antispam")   => antispam::handle_antispam(&bot, chat_id, arg0, &stores.db).await,
+                Some("spamlimits") => antispam::handle_spam_limits(&bot, chat_id, args, &stores.db).await,
+                Some("spamscore")  => antispam::handle_spam_score(&bot, chat_id, msg.reply_to_message.as_deref(), &stores.db).await,
+
+                // ── Approved users ────────────────────────────────────────
+                Some("approve")   => approvals::handle_approve(&bot, chat_id, user_id, &target, &stores.db, &stores.admins).await,
+                Some("unapprove") => approvals::handle_unapprove(&bot, chat_id, user_id, &target, &stores.db).await,
+                Some("approved")  => approvals::handle_approved(&bot, chat_id, &stores.db).await,
+
+                // ── Global bans ───────────────────────────────────────────
+                Some("gban")     => gbans::handle_gban(&bot, chat_id, user_id, &target, &stores.db, &stores.roles).await,
+                Some("ungban")   => gbans::handle_ungban(&bot, chat_id, &target, &stores.db).await,
+                Some("gbanstat") => gbans::handle_gbanstat(&bot, chat_id, arg0, &stores.db).await,
+
+                // ── Reports ───────────────────────────────────────────────
+                Some("report")   => reports::handle_report(&bot, &msg, rest_of_line, &stores.db, &stores.flood, &stores.admins).await,
+                Some("reportpm") => reports::handle_report_pm(&bot, chat_id, user_id, arg0, &stores.db, &stores.admins).await,
+
+                // ── Captcha ───────────────────────────────────────────────
+                Some("captcha")     => captcha::handle_captcha(&bot, chat_id, arg0, &stores.db).await,
+                Some("captchatime") => captcha::handle_captcha_time(&bot, chat_id, arg0, &stores.db).await,
+
+                // ── Join requests ─────────────────────────────────────────
+                Some("joinpolicy")    => join_requests::handle_join_policy(&bot, chat_id, arg0, &stores.db).await,
+                Some("joinquestions") => join_requests::handle_join_questions(&bot, chat_id, rest_of_line, &stores.db).await,
+
+                // ── Welcome / goodbye ─────────────────────────────────────
+                Some("setwelcome")   => greetings::handle_set_welcome(&bot, chat_id, chat_title, msg.from.as_deref(), rest_of_line, &stores.db).await,
+                Some("setgoodbye")   => greetings::handle_set_goodbye(&bot, chat_id, chat_title, msg.from.as_deref(), rest_of_line, &stores.db).await,
+                Some("cleanwelcome") => greetings::handle_clean_welcome(&bot, chat_id, arg0, &stores.db).await,
+                Some("cleanservice") => greetings::handle_clean_service(&bot, chat_id, arg0, &stores.db).await,
+
+                // ── Warnings ──────────────────────────────────────────────
+                Some("warn")       => warns::handle_warn(&bot, chat_id, user_id, &target, &stores.db, &stores.scheduler, &stores.admins).await,
+                Some("unwarn")     => warns::handle_unwarn(&bot, chat_id, user_id, &target, &stores.db).await,
+                Some("warns")      => warns::handle_warns(&bot, chat_id, &target, &stores.db).await,
+                Some("resetwarns") => warns::handle_reset_warns(&bot, chat_id, user_id, &target, &stores.db).await,
+                Some("warnlimit")  => warns::handle_warn_limit(&bot, chat_id, arg0, &stores.db).await,
+                Some("warnmode")   => warns::handle_warn_mode(&bot, chat_id, args, &stores.db).await,
+                Some("warntime")   => warns::handle_warn_time(&bot, chat_id, arg0, &stores.db).await,
 
                 // ── Filters ───────────────────────────────────────────────
-                "/filter"    => filters::handle_set_filter(&bot, chat_id, args, &stores.filter).await,
-                "/delfilter" => filters::handle_del_filter(&bot, chat_id, arg0, &stores.filter).await,
-                "/filters"   => filters::handle_list_filters(&bot, chat_id, &stores.filter).await,
+                Some("filter")    => filters::handle_set_filter(&bot, chat_id, args, &stores.db).await,
+                Some("delfilter") => filters::handle_del_filter(&bot, chat_id, arg0, &stores.db).await,
+                Some("filters")   => filters::handle_list_filters(&bot, chat_id, &stores.db).await,
 
                 // ── Notes ─────────────────────────────────────────────────
-                "/note"    => notes::handle_save_note(&bot, chat_id, args, &stores.note).await,
-                "/get"     => notes::handle_get_note(&bot, chat_id, arg0, &stores.note).await,
-                "/notes"   => notes::handle_list_notes(&bot, chat_id, &stores.note).await,
-                "/delnote" => notes::handle_del_note(&bot, chat_id, arg0, &stores.note).await,
+                Some("note")    => notes::handle_save_note(&bot, chat_id, args, &stores.db).await,
+                Some("get")     => notes::handle_get_note(&bot, chat_id, arg0, &stores.db).await,
+                Some("notes")   => notes::handle_list_notes(&bot, chat_id, &stores.db).await,
+                Some("delnote") => notes::handle_del_note(&bot, chat_id, arg0, &stores.db).await,
 
                 // ── Send / Post / Media ───────────────────────────────────
-                "/send"     => broadcast::handle_send(&bot, chat_id, rest_of_line).await,
-                "/post"     => broadcast::handle_post(&bot, chat_id, rest_of_line).await,
-                "/img"      => broadcast::handle_img(&bot, chat_id, args).await,
-                "/vid"      => broadcast::handle_vid(&bot, chat_id, args).await,
-                "/aud"      => broadcast::handle_aud(&bot, chat_id, args).await,
-                "/doc"      => broadcast::handle_doc(&bot, chat_id, args).await,
-                "/buttons"  => broadcast::handle_buttons_showcase(&bot, chat_id).await,
-                "/sendhelp" => broadcast::handle_send_help(&bot, chat_id).await,
+                Some("send")     => broadcast::handle_send(&bot, chat_id, rest_of_line).await,
+                Some("post")     => broadcast::handle_post(&bot, chat_id, rest_of_line).await,
+                Some("img")      => broadcast::handle_img(&bot, chat_id, args).await,
+                Some("vid")      => broadcast::handle_vid(&bot, chat_id, args).await,
+                Some("aud")      => broadcast::handle_aud(&bot, chat_id, args).await,
+                Some("doc")      => broadcast::handle_doc(&bot, chat_id, args).await,
+                Some("buttons")  => broadcast::handle_buttons_showcase(&bot, chat_id).await,
+                Some("sendhelp") => broadcast::handle_send_help(&bot, chat_id).await,
 
                 // ── Ping ──────────────────────────────────────────────────
-                "/ping" => handle_ping(&bot, chat_id, msg_date).await,
+                Some("ping") => handle_ping(&bot, chat_id, msg_date).await,
 
                 // ── System ────────────────────────────────────────────────
-                "/setcommands" => {
-                    match register_commands(&bot).await {
-                        Ok(_)  => { let _ = bot.send_message(chat_id, "✅ Commands registered!", None).await; }
-                        Err(e) => { let _ = bot.send_message(chat_id, format!("❌ Error: {}", e), None).await; }
-                    }
-                }
-                "/deletecommands" => {
-                    match bot.delete_my_commands(None).await {
-                        Ok(_)  => { let _ = bot.send_message(chat_id, "✅ Commands deleted!", None).await; }
-                        Err(e) => { let _ = bot.send_message(chat_id, format!("❌ Error: {}", e), None).await; }
-                    }
-                }
-                "/deletewebhook" => {
-                    match bot.delete_webhook(None).await {
-                        Ok(_)  => { let _ = bot.send_message(chat_id, "✅ Webhook deleted!", None).await; }
-                        Err(e) => { let _ = bot.send_message(chat_id, format!("❌ Error: {}", e), None).await; }
-                    }
-                }
+                Some("setcommands")    => system::handle_set_commands(&bot, chat_id, &stores.roles).await,
+                Some("deletecommands") => system::handle_delete_commands(&bot, chat_id, &stores.roles, &stores.db).await,
+                Some("deletewebhook")  => system::handle_delete_webhook(&bot, chat_id).await,
+                Some("addsudo")        => system::handle_add_sudo(&bot, chat_id, &target, &stores.roles).await,
+                Some("rmsudo")         => system::handle_rm_sudo(&bot, chat_id, &target, &stores.roles).await,
+                Some("sudolist")       => system::handle_sudo_list(&bot, chat_id, &stores.roles).await,
 
                 // ── Unknown command ───────────────────────────────────────
                 // Stay silent in groups/supergroups to avoid spamming.
-                _ if command.starts_with('/') => {
+                Some(name) => log::warn!("/{} is registered but has no handler", name),
+                None if command.starts_with('/') => {
                     if is_private {
                         let kb = InlineKeyboardMarkup { inline_keyboard: vec![vec![
                             InlineKeyboardButton { text: "📋 Menu".into(), callback_data: Some("main_menu".into()), ..Default::default() },
@@ -181,12 +314,17 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
                 }
 
                 // ── Plain text: check filters, then echo only in private ──
-                _ => {
-                    if !filters::check_filters(&bot, chat_id, text, &stores.filter).await {
-                        if is_private {
-                            handle_text_echo(&bot, chat_id, text, first_name).await;
-                        }
-                        // In groups: silently ignore unmatched plain text
+                None => {
+                    // A pending join questionnaire takes the applicant's next DM
+                    if is_private && join_requests::take_answers(&bot, user_id, text, &stores.db).await { return; }
+                    // "@admin" in reply to a message reports it
+                    if let Some(reason) = reports::admin_call(text).filter(|_| !is_private) {
+                        reports::handle_report(&bot, &msg, &reason, &stores.db, &stores.flood, &stores.admins).await;
+                        return;
+                    }
+                    // In groups: silently ignore unmatched plain text
+                    if !filters::check_filters(&bot, chat_id, text, &stores.db).await && is_private {
+                        handle_text_echo(&bot, chat_id, text, first_name).await;
                     }
                 }
             }
@@ -244,6 +382,15 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
         return;
     }
 
+    // ── Edited Message ────────────────────────────────────────────────────────
+    // Edits can't dodge the blocklist.
+    if let Some(msg) = update.edited_message {
+        if msg.chat.r#type == "group" || msg.chat.r#type == "supergroup" {
+            blocklist::check(&bot, &msg, &stores.db, &stores.scheduler, &stores.admins).await;
+        }
+        return;
+    }
+
     // ── Callback Query ────────────────────────────────────────────────────────
     if let Some(cq) = update.callback_query {
         let data       = cq.data.as_deref().unwrap_or("").to_string();
@@ -293,8 +440,29 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
             }
             _ => {}
         }
+        if let Some(rest) = data.strip_prefix("captcha:") {
+            captcha::handle_answer(&bot, &cq.id, chat_id, user_id, rest, &stores.db, &stores.scheduler).await;
+            return;
+        }
+        if let Some(rest) = data.strip_prefix("join:") {
+            join_requests::handle_review(&bot, &cq.id, user_id, &first_name, rest, &stores.db, &stores.admins).await;
+            return;
+        }
 
-        handle_callback(&bot, cq.id, chat_id, message_id, &data, user_id, &first_name).await;
+        if let Some(rest) = data.strip_prefix("report:") {
+            reports::handle_action(&bot, &cq.id, user_id, &first_name, rest, &stores.db, &stores.scheduler, &stores.admins).await;
+            return;
+        }
+        // A confirmed anonymous-admin command runs again as the admin who pressed
+        if let Some(rest) = data.strip_prefix("anon:") {
+            if let Some(msg) = anon_admin::confirm(&bot, &cq.id, &cq.from, rest, &stores.db, &stores.admins).await {
+                let update = tgbotrs::types::Update { message: Some(Box::new(msg)), edited_message: None, callback_query: None, ..update };
+                Box::pin(dispatch(bot, update, stores)).await;
+            }
+            return;
+        }
+
+        handle_callback(&bot, cq.id, chat_id, message_id, &data, user_id, &first_name, &stores.admins).await;
         return;
     }
 
@@ -330,8 +498,24 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
     // ── My Chat Member ────────────────────────────────────────────────────────
     if let Some(mcm) = update.my_chat_member {
         let chat_id = mcm.chat.id;
+        // The bot's own rights changed — drop the cached admin list.
+        stores.admins.invalidate(chat_id);
         let v = serde_json::to_value(&mcm.new_chat_member).unwrap_or_default();
         let status = v.get("status").and_then(|s| s.as_str()).unwrap_or("");
+        let old_v = serde_json::to_value(&mcm.old_chat_member).unwrap_or_default();
+        let old_status = old_v.get("status").and_then(|s| s.as_str()).unwrap_or("");
+        let is_group = mcm.chat.r#type == "group" || mcm.chat.r#type == "supergroup";
+        let present  = |s: &str| s == "member" || permissions::is_admin_status(s);
+        if is_group && present(status) && !present(old_status) {
+            // Joined a new group — remember it and give it fresh menus.
+            remember_chat(&stores.db, chat_id, mcm.chat.title.as_deref());
+            if let Err(e) = register_chat_commands(&bot, chat_id).await {
+                log::warn!("could not register commands in {}: {}", chat_id, e);
+            }
+            gbans::sweep(&bot, chat_id, &stores.db).await;
+        } else if is_group && !present(status) {
+            stores.db.delete(CHATS, &chat_id.to_string());
+        }
         if status == "member" || status == "administrator" {
             let p = SendMessageParams::new().parse_mode("HTML");
             let _ = bot.send_message(chat_id,
@@ -344,11 +528,37 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
     }
 
     // ── Chat Member ───────────────────────────────────────────────────────────
-    if let Some(cm) = update.chat_member { log::info!("chat_member: {}", cm.chat.id); return; }
+    if let Some(cm) = update.chat_member {
+        log::info!("chat_member: {}", cm.chat.id);
+        let old_v = serde_json::to_value(&cm.old_chat_member).unwrap_or_default();
+        let new_v = serde_json::to_value(&cm.new_chat_member).unwrap_or_default();
+        let was_admin = permissions::is_admin_status(old_v.get("status").and_then(|s| s.as_str()).unwrap_or(""));
+        let is_admin  = permissions::is_admin_status(new_v.get("status").and_then(|s| s.as_str()).unwrap_or(""));
+        // Any change to an admin (promote, demote, rights edit) invalidates.
+        if was_admin || is_admin {
+            stores.admins.invalidate(cm.chat.id);
+        }
+        // Joins and leaves for the captcha
+        let (was_in, is_in) = (is_present(&old_v), is_present(&new_v));
+        if let Ok(user) = serde_json::from_value::<tgbotrs::types::User>(new_v["user"].clone()) {
+            if !was_in && is_in {
+                targets::remember(&stores.db, &user);
+                if gbans::check(&bot, cm.chat.id, user.id, &stores.db).await { return; }
+                if !antiraid::on_join(&bot, cm.chat.id, &user, &stores.db, &stores.flood, &stores.scheduler, &stores.admins).await {
+                    captcha::on_join(&bot, cm.chat.id, &user, &stores.db, &stores.scheduler).await;
+                    antispam::on_join(&stores.db, cm.chat.id, &user);
+                }
+            } else if was_in && !is_in {
+                captcha::on_leave(&bot, cm.chat.id, user.id, &stores.db, &stores.scheduler).await;
+                antispam::on_leave(&stores.db, cm.chat.id, user.id);
+            }
+        }
+        return;
+    }
 
     // ── Chat Join Request ─────────────────────────────────────────────────────
     if let Some(jr) = update.chat_join_request {
-        let _ = bot.approve_chat_join_request(jr.chat.id, jr.from.id).await;
+        join_requests::on_request(&bot, &jr, &stores.db).await;
         return;
     }
 
@@ -356,7 +566,44 @@ pub async fn dispatch(bot: Bot, update: tgbotrs::types::Update, stores: Stores)
     if let Some(mr)  = update.message_reaction       { log::info!("reaction in {}", mr.chat.id); return; }
     if let Some(mrc) = update.message_reaction_count { log::info!("reaction_count in {}", mrc.chat.id); return; }
     if let Some(cb)  = update.chat_boost             { log::info!("boost in {}", cb.chat.id); return; }
-    if let Some(rcb) = update.removed_chat_boost     { log::info!("boost_removed in {}", rcb.chat.id); return; }
+    if let Some(rcb) = update.removed_chat_boost     { log::info!("boost_removed in {}", rcb.chat.id); }
+}
+
+// In the chat: members, admins, and restricted users still in the group.
+fn is_present(member: &serde_json::Value) -> bool {
+    match member.get("status").and_then(|s| s.as_str()).unwrap_or("") {
+        "member" | "administrator" | "creator" => true,
+        "restricted" => member.get("is_member").and_then(|b| b.as_bool()).unwrap_or(false),
+        _ => false,
+    }
+}
+
+// ── Chat directory ────────────────────────────────────────────────────────────
+//   Titles of the groups the bot is in, for menus and the audit log.
+
+fn remember_chat(db: &Db, chat_id: i64, title: Option<&str>) {
+    let key = chat_id.to_string();
+    let old = db.get(CHATS, &key);
+    if old.as_ref().and_then(|v| v.get("title")).and_then(|t| t.as_str()) != title || old.is_none() {
+        db.put(CHATS, &key, serde_json::json!({ "title": title }));
+    }
+}
+
+// ── Access gate ───────────────────────────────────────────────────────────────
+//   Applies the chat-type and permission requirements declared in the
+//   registry. Each check sends its own denial reply.
+
+async fn authorize(bot: &Bot, stores: &Stores, spec: &CommandSpec, chat_id: i64, user_id: i64, is_private: bool) -> bool {
+    if !spec.chats.allows(is_private) {
+        let p = SendMessageParams::new().parse_mode("HTML");
+        let _ = bot.send_message(chat_id, "⚠️ This command only works in groups.", Some(p)).await;
+        return false;
+    }
+    match spec.access {
+        Access::Anyone      => true,
+        Access::Chat(right) => permissions::require(bot, &stores.admins, chat_id, user_id, right).await,
+        Access::Bot(role)   => roles::require(bot, &stores.roles, chat_id, user_id, role, &format!("/{}", spec.name)).await,
+    }
 }
 
 // ── Text echo ─────────────────────────────────────────────────────────────────
@@ -372,7 +619,7 @@ async fn handle_text_echo(bot: &Bot, chat_id: i64, text: &str, first_name: &str)
     } else if lower.contains("thank") {
         format!("😊 You're welcome, {}! 🦀", first_name)
     } else {
-        format!("💬 <code>{}</code>\n\nUse /help or /menu!", html_escape(text))
+        format!("💬 <code>{}</code>\n\nUse /help or /menu!", html::escape(text))
     };
 
     let kb = InlineKeyboardMarkup { inline_keyboard: vec![vec![
@@ -408,7 +655,3 @@ async fn handle_stats(bot: &Bot, chat_id: i64) {
     let p = SendMessageParams::new().parse_mode("HTML").reply_markup(ReplyMarkup::InlineKeyboard(kb));
     let _ = bot.send_message(chat_id, text, Some(p)).await;
 }
-
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
-}
@@ -0,0 +1,253 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Durations
+//  Parsing and formatting of the durations moderators type:
+//    compound   90s  30m  2h  1d12h  1w  (units s m h d w)
+//    ISO-8601   PT30M  P1DT12H  P2W    (no years or months)
+//  Telegram treats restrictions shorter than 30 seconds or longer
+//  than 366 days as permanent, so those are rejected for bans/mutes.
+// ════════════════════════════════════════════════════════════════
+
+use std::fmt;
+
+pub const MINUTE: i64 = 60;
+pub const HOUR:   i64 = 60 * MINUTE;
+pub const DAY:    i64 = 24 * HOUR;
+pub const WEEK:   i64 = 7 * DAY;
+
+/// The `until_date` window Telegram honours.
+pub const MIN_RESTRICTION: i64 = 30;
+pub const MAX_RESTRICTION: i64 = 366 * DAY;
+
+/// Example list for usage hints (HTML).
+pub const EXAMPLES: &str = "<code>90s</code>, <code>30m</code>, <code>2h</code>, <code>1d12h</code>, <code>1w</code>, <code>PT30M</code>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationError {
+    Invalid,
+    Zero,
+    TooShort,
+    TooLong,
+}
+
+impl fmt::Display for DurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DurationError::Invalid  => write!(f, "not a valid duration"),
+            DurationError::Zero     => write!(f, "duration must be longer than zero"),
+            DurationError::TooShort => write!(f, "shorter than 30 seconds, which Telegram treats as permanent"),
+            DurationError::TooLong  => write!(f, "longer than 366 days, which Telegram treats as permanent"),
+        }
+    }
+}
+
+// ── Parsing ──────────────────────────────────────────────────────────────────
+
+/// Seconds in `s`; case-insensitive.
+pub fn parse(s: &str) -> Result<i64, DurationError> {
+    let s = s.trim().to_ascii_uppercase();
+    let secs = match s.strip_prefix('P') {
+        Some(iso) => parse_iso(iso)?,
+        None      => sum_units(&s, &[('S', 1), ('M', MINUTE), ('H', HOUR), ('D', DAY), ('W', WEEK)])?,
+    };
+    if secs == 0 { Err(DurationError::Zero) } else { Ok(secs) }
+}
+
+/// `parse`, limited to the window Telegram accepts for `until_date`.
+pub fn parse_restriction(s: &str) -> Result<i64, DurationError> {
+    match parse(s)? {
+        secs if secs < MIN_RESTRICTION => Err(DurationError::TooShort),
+        secs if secs > MAX_RESTRICTION => Err(DurationError::TooLong),
+        secs => Ok(secs),
+    }
+}
+
+/// Whether `word` was meant as a duration: starts with a digit, or is
+/// ISO-shaped (`P1…`, `PT…`). Such words are never taken as a reason,
+/// except ordinals like `2nd`.
+pub fn looks_like(word: &str) -> bool {
+    let mut chars = word.chars().map(|c| c.to_ascii_uppercase());
+    match chars.next() {
+        Some(c) if c.is_ascii_digit() => !is_ordinal(word),
+        Some('P') => match chars.next() {
+            Some('T') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+            Some(c)   => c.is_ascii_digit(),
+            None      => false,
+        },
+        _ => false,
+    }
+}
+
+/// `1st`, `2nd`, `3rd`, `11th`…
+fn is_ordinal(word: &str) -> bool {
+    let suffix = word.trim_start_matches(|c: char| c.is_ascii_digit()).to_ascii_lowercase();
+    suffix.len() < word.len() && ["st", "nd", "rd", "th"].contains(&suffix.as_str())
+}
+
+fn parse_iso(s: &str) -> Result<i64, DurationError> {
+    let (date, time) = match s.split_once('T') {
+        Some((_, "")) => return Err(DurationError::Invalid),
+        Some((d, t))  => (d, Some(t)),
+        None          => (s, None),
+    };
+    if date.is_empty() && time.is_none() { return Err(DurationError::Invalid); }
+    let mut secs = if date.is_empty() { 0 } else { sum_units(date, &[('D', DAY), ('W', WEEK)])? };
+    if let Some(t) = time {
+        let t = sum_units(t, &[('S', 1), ('M', MINUTE), ('H', HOUR)])?;
+        secs = secs.checked_add(t).ok_or(DurationError::TooLong)?;
+    }
+    Ok(secs)
+}
+
+/// Sums `<digits><unit>` pairs; every number needs a unit from `units`.
+fn sum_units(s: &str, units: &[(char, i64)]) -> Result<i64, DurationError> {
+    let mut total = 0i64;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = units.iter().find(|(u, _)| *u == c).map(|(_, secs)| *secs).ok_or(DurationError::Invalid)?;
+        if digits.is_empty() { return Err(DurationError::Invalid); }
+        let n: i64 = digits.parse().map_err(|_| DurationError::TooLong)?;
+        total = n.checked_mul(unit)
+            .and_then(|v| total.checked_add(v))
+            .ok_or(DurationError::TooLong)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || s.is_empty() { return Err(DurationError::Invalid); }
+    Ok(total)
+}
+
+// ── Formatting ───────────────────────────────────────────────────────────────
+
+/// Compact label that `parse` reads back: "7d", "1d12h", "2h30m", "45s".
+pub fn fmt(secs: i64) -> String {
+    if secs <= 0 { return "0s".to_string(); }
+    let mut out = String::new();
+    let mut left = secs;
+    for (unit, size) in [("d", DAY), ("h", HOUR), ("m", MINUTE), ("s", 1)] {
+        if left >= size {
+            out.push_str(&format!("{}{}", left / size, unit));
+            left %= size;
+        }
+    }
+    out
+}
+
+/// Coarse age in the largest whole unit: "45s", "12m", "5h", "3d".
+pub fn age(secs: i64) -> String {
+    match secs.max(0) {
+        s if s < MINUTE => format!("{}s", s),
+        s if s < HOUR   => format!("{}m", s / MINUTE),
+        s if s < DAY    => format!("{}h", s / HOUR),
+        s               => format!("{}d", s / DAY),
+    }
+}
+
+/// Current unix time in seconds.
+pub fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DurationError::*;
+
+    #[test]
+    fn single_units() {
+        for (input, secs) in [
+            ("45s", 45), ("30m", 30 * MINUTE), ("2h", 2 * HOUR), ("7d", 7 * DAY),
+            ("1w", WEEK), ("2W", 2 * WEEK), (" 90S ", 90),
+        ] {
+            assert_eq!(parse(input), Ok(secs), "{}", input);
+        }
+    }
+
+    #[test]
+    fn compound() {
+        for (input, secs) in [
+            ("1d12h", DAY + 12 * HOUR), ("2h30m", 2 * HOUR + 30 * MINUTE),
+            ("1w1d", WEEK + DAY), ("1h1h", 2 * HOUR), ("1m30s", 90),
+        ] {
+            assert_eq!(parse(input), Ok(secs), "{}", input);
+        }
+    }
+
+    #[test]
+    fn iso_8601() {
+        for (input, secs) in [
+            ("PT30M", 30 * MINUTE), ("P1DT2H", DAY + 2 * HOUR), ("P2W", 2 * WEEK),
+            ("PT45S", 45), ("pt1h30m", HOUR + 30 * MINUTE), ("P1D", DAY),
+        ] {
+            assert_eq!(parse(input), Ok(secs), "{}", input);
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        for input in [
+            "", "   ", "h", "10", "10x", "1h30", "1hh", "1.5h", "-1h", "ban",
+            "P", "PT", "P1DT", "P1H", "PT1D", "P1Y", "1h!", "2h reason",
+        ] {
+            assert_eq!(parse(input), Err(Invalid), "{:?}", input);
+        }
+        assert_eq!(parse("0s"), Err(Zero));
+        assert_eq!(parse("PT0M"), Err(Zero));
+    }
+
+    #[test]
+    fn restriction_window() {
+        assert_eq!(parse_restriction("29s"), Err(TooShort));
+        assert_eq!(parse_restriction("30s"), Ok(30));
+        assert_eq!(parse_restriction("366d"), Ok(MAX_RESTRICTION));
+        assert_eq!(parse_restriction("367d"), Err(TooLong));
+        assert_eq!(parse_restriction("366d1s"), Err(TooLong));
+        assert_eq!(parse_restriction("0s"), Err(Zero));
+    }
+
+    #[test]
+    fn overflow() {
+        // Fits in an i64, but far outside what Telegram accepts.
+        assert_eq!(parse("99999999999w"), Ok(99_999_999_999 * WEEK));
+        assert_eq!(parse("99999999999999w"), Err(TooLong));
+        assert_eq!(parse("99999999999999999999s"), Err(TooLong));
+        assert_eq!(parse(&format!("{}s1s", i64::MAX)), Err(TooLong));
+        assert_eq!(parse("P99999999999999W"), Err(TooLong));
+        assert_eq!(parse_restriction("99999999999w"), Err(TooLong));
+    }
+
+    #[test]
+    fn duration_shaped_words() {
+        for word in ["1h", "30", "1x", "PT30M", "P1D", "pt5m", "2h30"] {
+            assert!(looks_like(word), "{}", word);
+        }
+        for word in ["2nd", "1st", "3rd", "11th", "spam", "Please", "PT", "P", "Pt", "", "#1"] {
+            assert!(!looks_like(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn fmt_reads_back() {
+        for (secs, label) in [
+            (45, "45s"), (90, "1m30s"), (2 * HOUR, "2h"), (DAY + 12 * HOUR, "1d12h"),
+            (WEEK, "7d"), (MAX_RESTRICTION, "366d"), (0, "0s"), (-5, "0s"),
+        ] {
+            assert_eq!(fmt(secs), label);
+            if secs > 0 { assert_eq!(parse(label), Ok(secs)); }
+        }
+    }
+
+    #[test]
+    fn age_is_coarse() {
+        assert_eq!(age(59), "59s");
+        assert_eq!(age(MINUTE), "1m");
+        assert_eq!(age(DAY - 1), "23h");
+        assert_eq!(age(3 * DAY + HOUR), "3d");
+        assert_eq!(age(-10), "0s");
+    }
+}
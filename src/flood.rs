@@ -0,0 +1,97 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Flood Tracker
+//  Sliding windows of recent message times per (chat, user), of
+//  recent joins per chat (for anti-raid) and of recent /report uses,
+//  kept in memory only: a restart simply starts every window afresh.
+// ════════════════════════════════════════════════════════════════
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Above this many tracked users, idle windows are swept on insert.
+const SWEEP_AT: usize = 10_000;
+/// Longest window a chat can configure; older timestamps never matter.
+pub const MAX_WINDOW: Duration = Duration::from_secs(300);
+/// A join is reported by both the service message and `chat_member`;
+/// the same user joining again within this time is the same join.
+const JOIN_DEDUP: Duration = Duration::from_secs(30);
+
+/// Recent message times, oldest first.
+type Window = VecDeque<Instant>;
+/// Recent joins per chat as (time, user), oldest first.
+type JoinLog = VecDeque<(Instant, i64)>;
+
+#[derive(Clone, Default)]
+pub struct FloodTracker {
+    inner: Arc<Mutex<HashMap<(i64, i64), Window>>>,
+    joins: Arc<Mutex<HashMap<i64, JoinLog>>>,
+    reports: Arc<Mutex<HashMap<(i64, i64), Window>>>,
+}
+
+impl FloodTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a message and returns true once `limit` messages fall inside
+    /// `window`. The user's window is cleared when it fires, so one burst
+    /// triggers the action once.
+    pub fn hit(&self, chat_id: i64, user_id: i64, limit: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        let mut map = self.inner.lock().unwrap();
+        if map.len() > SWEEP_AT {
+            map.retain(|_, times| times.back().is_some_and(|t| now.duration_since(*t) < MAX_WINDOW));
+        }
+        let times = map.entry((chat_id, user_id)).or_default();
+        times.push_back(now);
+        while times.front().is_some_and(|t| now.duration_since(*t) > window) {
+            times.pop_front();
+        }
+        if times.len() >= limit {
+            map.remove(&(chat_id, user_id));
+            return true;
+        }
+        false
+    }
+
+    /// Records a join. `None` if this join was already seen; otherwise
+    /// whether the chat has now had `limit` joins within `window`
+    /// (never with `limit` 0).
+    pub fn join(&self, chat_id: i64, user_id: i64, limit: usize, window: Duration) -> Option<bool> {
+        let now = Instant::now();
+        let mut map = self.joins.lock().unwrap();
+        let log = map.entry(chat_id).or_default();
+        while log.front().is_some_and(|(t, _)| now.duration_since(*t) > MAX_WINDOW.max(JOIN_DEDUP)) {
+            log.pop_front();
+        }
+        if log.iter().any(|(t, u)| *u == user_id && now.duration_since(*t) < JOIN_DEDUP) {
+            return None;
+        }
+        log.push_back((now, user_id));
+        let recent = log.iter().filter(|(t, _)| now.duration_since(*t) <= window).count();
+        Some(limit > 0 && recent >= limit)
+    }
+
+    /// Counts a report by `user_id` unless they already made `limit`
+    /// within `window`; returns false (and counts nothing) in that case.
+    pub fn report(&self, chat_id: i64, user_id: i64, limit: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        let mut map = self.reports.lock().unwrap();
+        if map.len() > SWEEP_AT {
+            map.retain(|_, times| times.back().is_some_and(|t| now.duration_since(*t) < window));
+        }
+        let times = map.entry((chat_id, user_id)).or_default();
+        while times.front().is_some_and(|t| now.duration_since(*t) > window) {
+            times.pop_front();
+        }
+        if times.len() >= limit { return false; }
+        times.push_back(now);
+        true
+    }
+
+    /// Forgets every window in a chat, e.g. after its settings change.
+    pub fn reset(&self, chat_id: i64) {
+        self.inner.lock().unwrap().retain(|(chat, _), _| *chat != chat_id);
+    }
+}
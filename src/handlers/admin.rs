@@ -8,11 +8,13 @@ use tgbotrs::{
     types::InlineKeyboardMarkup,
     Bot, ChatId, ReplyMarkup,
 };
+use super::audit::{self, Action, Entry};
 use super::commands::btn;
-
-pub fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
-}
+use crate::admin_cache::AdminCache;
+use crate::html;
+use crate::permissions;
+use crate::storage::Db;
+use crate::targets::{self, Lookup, Resolved};
 
 async fn reply(bot: &Bot, chat_id: i64, text: &str) {
     let kb = InlineKeyboardMarkup { inline_keyboard: vec![vec![btn("⬅️ Menu", "main_menu")]] };
@@ -20,34 +22,13 @@ async fn reply(bot: &Bot, chat_id: i64, text: &str) {
     let _ = bot.send_message(chat_id, text, Some(p)).await;
 }
 
-fn resolve<'a>(
-    reply_id: Option<i64>, reply_name: Option<&'a str>, args: &'a [&'a str],
-) -> Option<(i64, String, &'a [&'a str])> {
-    if let Some(id) = reply_id {
-        return Some((id, reply_name.unwrap_or("User").to_string(), args));
-    }
-    if let Some(&first) = args.first() {
-        if let Ok(id) = first.parse::<i64>() {
-            return Some((id, first.to_string(), &args[1..]));
-        }
-    }
-    None
-}
-
 // ── /promote [user_id] [Title] ────────────────────────────────────────────────
 
-pub async fn handle_promote(
-    bot: &Bot, chat_id: i64,
-    reply_user_id: Option<i64>, reply_user_name: Option<&str>, args: &[&str],
-) {
-    let Some((tid, tname, rest)) = resolve(reply_user_id, reply_user_name, args) else {
-        reply(bot, chat_id,
-            "⚠️ <b>Usage:</b>\n\
-            • Reply: <code>/promote</code> or <code>/promote 🛡️ Guard</code>\n\
-            • By ID: <code>/promote 123456789 🛡️ Guard</code>").await;
-        return;
-    };
-    let custom_title: Option<String> = if rest.is_empty() { None } else { Some(rest.join(" ")) };
+pub async fn handle_promote(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/promote", "[title]").await else { return };
+    let (tid, tname) = (t.id, &t.name);
+    if !permissions::guard_target(bot, admins, chat_id, tid, true).await { return; }
+    let custom_title: Option<String> = if target.rest.is_empty() { None } else { Some(target.rest.clone()) };
 
     let params = PromoteChatMemberParams::new()
         .can_manage_chat(true).can_delete_messages(true).can_manage_video_chats(true)
@@ -56,16 +37,18 @@ pub async fn handle_promote(
 
     match bot.promote_chat_member(ChatId::from(chat_id), tid, Some(params)).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Promote)
+                .target(t).reason(custom_title.as_deref().unwrap_or(""))).await;
             let title_line = if let Some(ref t) = custom_title {
                 let _ = bot.set_chat_administrator_custom_title(ChatId::from(chat_id), tid, t.clone()).await;
-                format!("\n🏷️ <b>Title:</b> <i>{}</i>", html_escape(t))
+                format!("\n🏷️ <b>Title:</b> <i>{}</i>", html::escape(t))
             } else { String::new() };
             reply(bot, chat_id, &format!(
                 "⭐ <b>Promoted!</b>\n\n\
                 👤 <a href=\"tg://user?id={tid}\">{name}</a>{title}\n\n\
                 ✅ Manage chat · Delete messages · Restrict members\n\
                 ✅ Invite users · Change info · Pin messages · Stories",
-                tid=tid, name=html_escape(&tname), title=title_line
+                tid=tid, name=html::escape(tname), title=title_line
             )).await;
         }
         Err(e) => reply(bot, chat_id, &format!(
@@ -75,57 +58,53 @@ pub async fn handle_promote(
 
 // ── /demote [user_id] ─────────────────────────────────────────────────────────
 
-pub async fn handle_demote(
-    bot: &Bot, chat_id: i64,
-    reply_user_id: Option<i64>, reply_user_name: Option<&str>, args: &[&str],
-) {
-    let Some((tid, tname, _)) = resolve(reply_user_id, reply_user_name, args) else {
-        reply(bot, chat_id,
-            "⚠️ <b>Usage:</b>\n• Reply: <code>/demote</code>\n• By ID: <code>/demote 123456789</code>").await;
-        return;
-    };
+pub async fn handle_demote(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/demote", "").await else { return };
+    let (tid, tname) = (t.id, &t.name);
+    if !permissions::guard_target(bot, admins, chat_id, tid, true).await { return; }
     let params = PromoteChatMemberParams::new()
         .can_manage_chat(false).can_delete_messages(false).can_manage_video_chats(false)
         .can_restrict_members(false).can_invite_users(false).can_change_info(false)
         .can_pin_messages(false).can_promote_members(false).can_post_stories(false)
         .can_edit_stories(false).can_delete_stories(false);
     match bot.promote_chat_member(ChatId::from(chat_id), tid, Some(params)).await {
-        Ok(_) => reply(bot, chat_id, &format!(
-            "🔽 <b>Demoted!</b>\n\n\
-            👤 <a href=\"tg://user?id={tid}\">{name}</a> is now a regular member.",
-            tid=tid, name=html_escape(&tname)
-        )).await,
+        Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Demote).target(t)).await;
+            reply(bot, chat_id, &format!(
+                "🔽 <b>Demoted!</b>\n\n\
+                👤 <a href=\"tg://user?id={tid}\">{name}</a> is now a regular member.",
+                tid=tid, name=html::escape(tname)
+            )).await
+        }
         Err(e) => reply(bot, chat_id, &format!("❌ <b>Demote failed:</b> <code>{}</code>", e)).await,
     }
 }
 
 // ── /title [user_id] <Title> ──────────────────────────────────────────────────
 
-pub async fn handle_title(
-    bot: &Bot, chat_id: i64,
-    reply_user_id: Option<i64>, reply_user_name: Option<&str>, args: &[&str],
-) {
-    let Some((tid, tname, rest)) = resolve(reply_user_id, reply_user_name, args) else {
-        reply(bot, chat_id,
-            "⚠️ <b>Usage:</b>\n• Reply: <code>/title 🛡️ Guardian</code>\n• By ID: <code>/title 123456789 🛡️ Guardian</code>").await;
-        return;
-    };
-    if rest.is_empty() {
-        reply(bot, chat_id, "⚠️ Provide a title. Example: <code>/title 🛡️ Guardian</code>").await;
+pub async fn handle_title(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/title", "<title>").await else { return };
+    let (tid, tname) = (t.id, &t.name);
+    if !permissions::guard_target(bot, admins, chat_id, tid, true).await { return; }
+    if target.rest.is_empty() {
+        reply(bot, chat_id, "⚠️ Provide a title. Example: <code>/title @user 🛡️ Guardian</code>").await;
         return;
     }
-    let title = rest.join(" ");
+    let title = target.rest.clone();
     if title.chars().count() > 16 {
         reply(bot, chat_id, "⚠️ Title must be max 16 characters.").await;
         return;
     }
     match bot.set_chat_administrator_custom_title(ChatId::from(chat_id), tid, title.clone()).await {
-        Ok(_) => reply(bot, chat_id, &format!(
-            "🏷️ <b>Title set!</b>\n\n\
-            👤 <a href=\"tg://user?id={tid}\">{name}</a>\n\
-            🔖 <i>{title}</i>",
-            tid=tid, name=html_escape(&tname), title=html_escape(&title)
-        )).await,
+        Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Title).target(t).reason(&title)).await;
+            reply(bot, chat_id, &format!(
+                "🏷️ <b>Title set!</b>\n\n\
+                👤 <a href=\"tg://user?id={tid}\">{name}</a>\n\
+                🔖 <i>{title}</i>",
+                tid=tid, name=html::escape(tname), title=html::escape(&title)
+            )).await
+        }
         Err(e) => reply(bot, chat_id, &format!(
             "❌ <b>Failed:</b> <code>{}</code>\n<i>User must already be an admin.</i>", e)).await,
     }
@@ -133,42 +112,38 @@ pub async fn handle_title(
 
 // ── /userinfo [user_id | @username | reply] ───────────────────────────────────
 
-pub async fn handle_userinfo(
-    bot: &Bot, chat_id: i64,
-    reply_user_id: Option<i64>, reply_user_name: Option<&str>, arg: Option<&str>,
-) {
-    let target_id: i64 = if let Some(id) = reply_user_id {
-        id
-    } else if let Some(a) = arg {
-        if let Ok(id) = a.parse::<i64>() { id }
-        else if a.starts_with('@') {
-            match bot.get_chat(a).await {
-                Ok(info) => info.id,
-                Err(_) => {
-                    reply(bot, chat_id, &format!(
-                        "❌ Could not resolve <code>{}</code>.\n<i>Only works for public users/chats.</i>",
-                        html_escape(a))).await;
-                    return;
-                }
+pub async fn handle_userinfo(bot: &Bot, chat_id: i64, target: &Resolved, admins: &AdminCache) {
+    // Usernames the directory hasn't seen may still be public — ask Telegram.
+    let (target_id, fallback_name) = match &target.target {
+        Lookup::Unknown(name) => match bot.get_chat(name.as_str()).await {
+            Ok(info) => (info.id, name.clone()),
+            Err(_) => {
+                reply(bot, chat_id, &format!(
+                    "❌ Could not resolve <code>{}</code>.\n<i>Only works for users I've seen or public chats.</i>",
+                    html::escape(name))).await;
+                return;
             }
-        } else {
-            reply(bot, chat_id,
-                "⚠️ <b>Usage:</b>\n• Reply to a message: <code>/userinfo</code>\n\
-                • By ID: <code>/userinfo 123456789</code>\n• By username: <code>/userinfo @user</code>").await;
-            return;
-        }
-    } else {
-        reply(bot, chat_id,
-            "⚠️ <b>Usage:</b>\n• Reply to a message: <code>/userinfo</code>\n\
-            • By ID: <code>/userinfo 123456789</code>\n• By username: <code>/userinfo @user</code>").await;
-        return;
+        },
+        _ => match targets::require(bot, chat_id, target, "/userinfo", "").await {
+            Some(t) => (t.id, t.name.clone()),
+            None => return,
+        },
     };
 
-    match bot.get_chat_member(ChatId::from(chat_id), target_id).await {
+    // Admins come from the cache; everyone else needs a live lookup for
+    // their exact status (restricted, left, banned).
+    let cached = admins.admins(bot, chat_id).await.ok()
+        .and_then(|list| list.into_iter().find(|m| m.user_id() == target_id));
+    let member = match cached {
+        Some(m) => Ok(m),
+        None    => permissions::fetch_member(bot, chat_id, target_id).await,
+    };
+
+    match member {
         Ok(member) => {
-            let v       = serde_json::to_value(&member).unwrap_or_default();
+            let v       = member.raw();
             let user    = v.get("user").cloned().unwrap_or_default();
-            let first   = user.get("first_name").and_then(|n| n.as_str()).unwrap_or(reply_user_name.unwrap_or("Unknown"));
+            let first   = user.get("first_name").and_then(|n| n.as_str()).unwrap_or(&fallback_name);
             let last    = user.get("last_name").and_then(|n| n.as_str()).unwrap_or("");
             let uname   = user.get("username").and_then(|n| n.as_str());
             let is_bot  = user.get("is_bot").and_then(|b| b.as_bool()).unwrap_or(false);
@@ -176,8 +151,8 @@ pub async fn handle_userinfo(
             let status  = v.get("status").and_then(|s| s.as_str()).unwrap_or("unknown");
             let ctitle  = v.get("custom_title").and_then(|s| s.as_str());
 
-            let full_name = if last.is_empty() { html_escape(first) }
-                            else { format!("{} {}", html_escape(first), html_escape(last)) };
+            let full_name = if last.is_empty() { html::escape(first) }
+                            else { format!("{} {}", html::escape(first), html::escape(last)) };
 
             let status_label = match status {
                 "creator"       => "👑 Creator",
@@ -197,7 +172,7 @@ pub async fn handle_userinfo(
                 full_name, target_id, status_label
             );
             if let Some(u) = uname   { text.push_str(&format!("\n<b>Username:</b> @{}", u)); }
-            if let Some(t) = ctitle  { text.push_str(&format!("\n<b>Admin Title:</b> <i>{}</i>", html_escape(t))); }
+            if let Some(t) = ctitle  { text.push_str(&format!("\n<b>Admin Title:</b> <i>{}</i>", html::escape(t))); }
             if premium { text.push_str("\n<b>Premium:</b> 💎"); }
             if is_bot  { text.push_str("\n<b>Type:</b> 🤖 Bot"); }
             text.push_str(&format!("\n\n<a href=\"tg://user?id={}\">📨 Open chat</a>", target_id));
@@ -210,3 +185,34 @@ pub async fn handle_userinfo(
             "❌ <b>Could not get user info:</b> <code>{}</code>\n<i>User must be a member of this chat.</i>", e)).await,
     }
 }
+
+// ── /admincache — force-refresh the cached admin list ─────────────────────────
+
+const ADMINCACHE_COOLDOWN_SECS: u64 = 30;
+
+pub async fn handle_admincache(bot: &Bot, chat_id: i64, user_id: i64, admins: &AdminCache) {
+    // Check against the cached list, so non-admins can't force API calls.
+    match admins.lookup(bot, chat_id, user_id).await {
+        Ok(m) if m.is_admin() => {}
+        Ok(_) => {
+            reply(bot, chat_id, "🚫 Only chat admins can refresh the admin list.").await;
+            return;
+        }
+        Err(e) => {
+            reply(bot, chat_id, &format!("❌ <b>Could not check admins:</b> <code>{}</code>", e)).await;
+            return;
+        }
+    }
+    if let Some(age) = admins.age(chat_id) {
+        if age < ADMINCACHE_COOLDOWN_SECS {
+            reply(bot, chat_id, &format!(
+                "⏳ Admin list was refreshed <b>{}s</b> ago. Try again in a moment.", age)).await;
+            return;
+        }
+    }
+    match admins.refresh(bot, chat_id).await {
+        Ok(list) => reply(bot, chat_id, &format!(
+            "🔄 <b>Admin cache refreshed!</b>\n\n👑 {} admin(s) loaded.", list.len())).await,
+        Err(e) => reply(bot, chat_id, &format!("❌ <b>Refresh failed:</b> <code>{}</code>", e)).await,
+    }
+}
@@ -0,0 +1,127 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Anonymous Admins
+//  A command sent by an anonymous admin arrives from the group
+//  itself, so there is no user whose rights can be checked. Admin
+//  commands from anonymous admins are held, and a button asks a
+//  real admin with the command's right to confirm. The command
+//  then runs as that admin.
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::{AnswerCallbackQueryParams, EditMessageTextParams, SendMessageParams},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, Message, User},
+    Bot, ChatId, ReplyMarkup,
+};
+
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::html;
+use crate::registry::{self, Access, CommandSpec};
+use crate::storage::{chat_key, chat_prefix, Db, ANON_COMMANDS};
+
+/// Unconfirmed commands older than this are dropped.
+const PENDING_TTL: i64 = 10 * duration::MINUTE;
+const EXCERPT_LEN: usize = 200;
+
+/// A held command, keyed by `chat_key(chat, message_id)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Pending {
+    msg: Message,
+    at: i64,
+    /// The confirmation prompt.
+    prompt: i64,
+}
+
+fn excerpt(text: &str) -> String {
+    match text.char_indices().nth(EXCERPT_LEN) {
+        Some((i, _)) => format!("{}…", &text[..i]),
+        None => text.to_string(),
+    }
+}
+
+async fn edit_prompt(bot: &Bot, chat_id: i64, prompt: i64, text: &str) {
+    let p = EditMessageTextParams::new().chat_id(ChatId::from(chat_id)).message_id(prompt).parse_mode("HTML");
+    let _ = bot.edit_message_text(text, Some(p)).await;
+}
+
+/// Whether `msg` was sent by an anonymous admin of its own chat.
+pub fn is_anonymous(msg: &Message) -> bool {
+    msg.sender_chat.as_ref().is_some_and(|c| c.id == msg.chat.id)
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Holding — called instead of the access check
+// ════════════════════════════════════════════════════════════════
+
+pub async fn hold(bot: &Bot, msg: &Message, spec: &CommandSpec, db: &Db) {
+    let chat_id = msg.chat.id;
+    let now = duration::now();
+    for (key, p) in db.load_all::<Pending>(ANON_COMMANDS, &chat_prefix(chat_id)) {
+        if now - p.at > PENDING_TTL { db.delete(ANON_COMMANDS, &key); }
+    }
+
+    let data = format!("anon:{}:{}", chat_id, msg.message_id);
+    let kb = InlineKeyboardMarkup { inline_keyboard: vec![vec![
+        InlineKeyboardButton { text: "✅ Confirm".into(), callback_data: Some(data), ..Default::default() },
+    ]]};
+    let p = SendMessageParams::new().parse_mode("HTML").reply_markup(ReplyMarkup::InlineKeyboard(kb));
+    let text = format!(
+        "🕶 <b>Anonymous admin</b>\n<code>{}</code>\n\n\
+        <i>An admin who can use /{} must confirm within {}.</i>",
+        html::escape(&excerpt(msg.text.as_deref().unwrap_or(""))), spec.name, duration::fmt(PENDING_TTL));
+    match bot.send_message(chat_id, text, Some(p)).await {
+        Ok(prompt) => db.save(ANON_COMMANDS, &chat_key(chat_id, msg.message_id),
+            &Pending { msg: msg.clone(), at: now, prompt: prompt.message_id }),
+        Err(e) => log::warn!("anon: could not ask for confirmation in {}: {}", chat_id, e),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Confirm button — `anon:<chat>:<message>`
+//  Returns the held command as if the presser had sent it.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn confirm(bot: &Bot, cq_id: &str, presser: &User, data: &str, db: &Db, admins: &AdminCache) -> Option<Message> {
+    let toast = |text: &'static str| {
+        let p = AnswerCallbackQueryParams::new().text(text);
+        bot.answer_callback_query(cq_id, Some(p))
+    };
+    let (chat_id, message_id) = data.split_once(':')?;
+    let (Ok(chat_id), Ok(message_id)) = (chat_id.parse::<i64>(), message_id.parse::<i64>()) else { return None };
+    let key = chat_key(chat_id, message_id);
+
+    let Some(p) = db.load::<Pending>(ANON_COMMANDS, &key) else {
+        let _ = toast("ℹ️ Already handled.").await;
+        return None;
+    };
+    if duration::now() - p.at > PENDING_TTL {
+        db.delete(ANON_COMMANDS, &key);
+        edit_prompt(bot, chat_id, p.prompt, "⌛ <i>Anonymous admin command expired.</i>").await;
+        let _ = toast("⌛ This command expired.").await;
+        return None;
+    }
+
+    let command = p.msg.text.as_deref().unwrap_or("")
+        .split_whitespace().next().unwrap_or("")
+        .trim_start_matches('/').split('@').next().unwrap_or("");
+    let Some(Access::Chat(right)) = registry::find(command).map(|s| s.access) else { return None };
+    match admins.lookup(bot, chat_id, presser.id).await {
+        Ok(m) if m.has(right) => {}
+        Ok(m) if m.is_admin() => { let _ = toast("🚫 You don't have the admin right for that.").await; return None; }
+        _ => { let _ = toast("🚫 Only admins can confirm this.").await; return None; }
+    }
+    // Two admins pressing at once: only the one who removes it runs it.
+    if !db.delete(ANON_COMMANDS, &key) { return None; }
+
+    let _ = toast("✅ Confirmed").await;
+    edit_prompt(bot, chat_id, p.prompt, &format!(
+        "🕶 <code>{}</code>\n\n✅ <i>Confirmed by</i> <a href=\"tg://user?id={}\">{}</a>",
+        html::escape(&excerpt(p.msg.text.as_deref().unwrap_or(""))), presser.id, html::escape(&presser.first_name))).await;
+    log::info!("anon: /{} in {} confirmed by {}", command, chat_id, presser.id);
+
+    let mut msg = p.msg;
+    msg.from = Some(Box::new(presser.clone()));
+    msg.sender_chat = None;
+    Some(msg)
+}
@@ -0,0 +1,134 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Antiflood
+//  N messages within M seconds from one user runs the chat's flood
+//  action (mute, kick, ban). Windows slide per user (flood.rs).
+//  Admins, approved users, anonymous admins and the linked channel
+//  are exempt.
+//  /flood   /setflood <n> [window]|off   /floodmode <action>
+// ════════════════════════════════════════════════════════════════
+
+use std::time::Duration;
+
+use tgbotrs::{types::Message, Bot};
+
+use super::approvals;
+use super::warns;
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::flood::{FloodTracker, MAX_WINDOW};
+use crate::html::{self, reply};
+use crate::scheduler::Scheduler;
+use crate::settings::{self, FloodPolicy};
+use crate::storage::Db;
+use crate::targets::Target;
+
+const MIN_LIMIT: u32 = 2;
+const MAX_LIMIT: u32 = 50;
+
+fn describe(p: &FloodPolicy) -> String {
+    match p.limit {
+        0 => "off".to_string(),
+        n => format!("{} messages in {} → {}", n, duration::fmt(p.window), warns::action_label(p.action)),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Enforcement — called for every group message
+//  Returns true if the sender was punished for flooding.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn check(bot: &Bot, msg: &Message, db: &Db, flood: &FloodTracker, sched: &Scheduler, admins: &AdminCache) -> bool {
+    let chat_id = msg.chat.id;
+    let policy = settings::load(db, chat_id).flood;
+    if policy.limit == 0 { return false; }
+    // Posts as a chat (anonymous admins, channels) and join/leave notices don't count.
+    if msg.sender_chat.is_some() || msg.new_chat_members.is_some() || msg.left_chat_member.is_some() { return false; }
+    let Some(from) = &msg.from else { return false };
+
+    let window = Duration::from_secs(policy.window.max(1) as u64);
+    if !flood.hit(chat_id, from.id, policy.limit as usize, window) { return false; }
+    if approvals::exempt(bot, chat_id, from.id, db, admins).await { return false; }
+
+    let t = Target { id: from.id, name: from.first_name.clone() };
+    let reason = format!("flood: {} messages in {}", policy.limit, duration::fmt(policy.window));
+    match warns::punish(bot, chat_id, bot.me.id, &t, policy.action, &reason, db, sched).await {
+        Ok(_) => {
+            reply(bot, chat_id, &format!(
+                "🌊 <a href=\"tg://user?id={}\">{}</a> is flooding the chat — <b>{}</b>.",
+                t.id, html::escape(&t.name), warns::action_label(policy.action))).await;
+            true
+        }
+        Err(e) => {
+            log::warn!("antiflood: {} on {} in {} failed: {}", warns::action_label(policy.action), t.id, chat_id, e);
+            false
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /flood   /setflood <n> [window]|off   /floodmode <action>
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_flood(bot: &Bot, chat_id: i64, db: &Db) {
+    let p = settings::load(db, chat_id).flood;
+    reply(bot, chat_id, &format!(
+        "🌊 Antiflood: <b>{}</b>\n\n\
+        <i>Change with</i> <code>/setflood 5 10s</code>, <code>/setflood off</code> <i>and</i> <code>/floodmode mute 1h</code>",
+        describe(&p))).await;
+}
+
+pub async fn handle_set_flood(bot: &Bot, chat_id: i64, args: &[&str], db: &Db, flood: &FloodTracker) {
+    const USAGE: &str = "⚠️ <b>Usage:</b> <code>/setflood &lt;messages&gt; [window]</code> or <code>/setflood off</code>\n\
+        <i>Example:</i> <code>/setflood 5 10s</code>";
+    let limit = match args.first().map(|a| a.to_lowercase()).as_deref() {
+        None => { reply(bot, chat_id, USAGE).await; return; }
+        Some("off" | "0") => 0,
+        Some(n) => match n.parse::<u32>() {
+            Ok(n) if (MIN_LIMIT..=MAX_LIMIT).contains(&n) => n,
+            _ => {
+                reply(bot, chat_id, &format!("⚠️ The message count must be from {} to {}.", MIN_LIMIT, MAX_LIMIT)).await;
+                return;
+            }
+        },
+    };
+    let window = match args.get(1) {
+        None => None,
+        Some(w) => match duration::parse(w) {
+            Ok(secs) if secs as u64 <= MAX_WINDOW.as_secs() => Some(secs),
+            Ok(_) => {
+                reply(bot, chat_id, &format!("⚠️ The window can be at most {}.", duration::fmt(MAX_WINDOW.as_secs() as i64))).await;
+                return;
+            }
+            Err(e) => {
+                reply(bot, chat_id, &format!("⚠️ <code>{}</code>: {}\n\n{}", html::escape(w), e, USAGE)).await;
+                return;
+            }
+        },
+    };
+    let p = settings::update(db, chat_id, |s| {
+        s.flood.limit = limit;
+        if let Some(w) = window { s.flood.window = w; }
+    }).flood;
+    flood.reset(chat_id);
+    reply(bot, chat_id, &format!("✅ Antiflood: <b>{}</b>.", describe(&p))).await;
+}
+
+pub async fn handle_flood_mode(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    if args.is_empty() {
+        let p = settings::load(db, chat_id).flood;
+        reply(bot, chat_id, &format!(
+            "🌊 Flood action: <b>{}</b>\n\n\
+            <i>Options:</i> <code>mute [duration]</code> · <code>kick</code> · <code>ban</code> · <code>tban &lt;duration&gt;</code>",
+            warns::action_label(p.action))).await;
+        return;
+    }
+    match warns::parse_action(args) {
+        Ok(action) => {
+            settings::update(db, chat_id, |s| s.flood.action = action);
+            reply(bot, chat_id, &format!("✅ Flooders now get: <b>{}</b>.", warns::action_label(action))).await;
+        }
+        Err(None) => reply(bot, chat_id,
+            "⚠️ <b>Usage:</b> <code>/floodmode mute [duration]|kick|ban|tban &lt;duration&gt;</code>").await,
+        Err(Some(why)) => reply(bot, chat_id, &format!("⚠️ <b>Invalid duration</b> {}", why)).await,
+    }
+}
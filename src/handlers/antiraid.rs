@@ -0,0 +1,249 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Anti-raid
+//  While raid mode is on, every new member is kicked (or muted until
+//  it ends), join requests are parked, and admins are alerted. It
+//  starts with /antiraid or when joins arrive faster than the chat's
+//  /autoraid rate, and switches itself off on a scheduler timer.
+//  Approved users are let in as usual.
+//  /antiraid [duration|off]   /raidmode <kick|mute>
+//  /autoraid <joins> [window]|off
+// ════════════════════════════════════════════════════════════════
+
+use std::time::Duration;
+
+use tgbotrs::{
+    gen_methods::RestrictChatMemberParams,
+    types::User,
+    Bot, ChatId,
+};
+
+use super::approvals;
+use super::audit::{self, Action, Entry};
+use super::join_requests;
+use super::moderation::no_perms;
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::flood::{FloodTracker, MAX_WINDOW};
+use crate::html::{self, reply};
+use crate::scheduler::{Job, Scheduler};
+use crate::settings::{self, RaidAction, RaidPolicy};
+use crate::storage::Db;
+
+/// Longest raid mode; anything longer is better served by /lock or /ro.
+const MAX_DURATION: i64 = 7 * duration::DAY;
+const MIN_AUTO_JOINS: u32 = 3;
+const MAX_AUTO_JOINS: u32 = 500;
+
+fn action_label(action: RaidAction) -> &'static str {
+    match action {
+        RaidAction::Kick => "kicked",
+        RaidAction::Mute => "muted until it ends",
+    }
+}
+
+fn auto_label(p: &RaidPolicy) -> String {
+    match p.auto_joins {
+        0 => "off".to_string(),
+        n => format!("{} joins in {}", n, duration::fmt(p.auto_window)),
+    }
+}
+
+/// Invisible mentions, so every human admin gets a notification.
+pub async fn ping_admins(bot: &Bot, chat_id: i64, admins: &AdminCache) -> String {
+    admins.admins(bot, chat_id).await.unwrap_or_default().iter()
+        .filter(|m| !m.raw()["user"]["is_bot"].as_bool().unwrap_or(false))
+        .map(|m| format!("<a href=\"tg://user?id={}\">\u{200b}</a>", m.user_id()))
+        .collect()
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Start / end
+// ════════════════════════════════════════════════════════════════
+
+/// Switches raid mode on (or extends it) for `secs` and alerts the chat.
+/// `trigger` explains an automatic start.
+#[allow(clippy::too_many_arguments)]
+pub async fn start(bot: &Bot, chat_id: i64, by: i64, secs: i64, trigger: Option<&str>, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    let now = duration::now();
+    let was_active = settings::load(db, chat_id).raid.active(now);
+    let p = settings::update(db, chat_id, |s| {
+        if !s.raid.active(now) { s.raid.caught = 0; }
+        s.raid.until = Some(now + secs);
+    }).raid;
+    sched.schedule(chat_id, secs, Job::RaidEnd);
+    audit::record(bot, db, Entry::new(chat_id, by, Action::RaidOn).duration(Some(secs)).reason(trigger.unwrap_or(""))).await;
+
+    let why = trigger.map(|t| format!("\n<b>Trigger:</b> {}", html::escape(t))).unwrap_or_default();
+    let verb = if was_active { "extended" } else { "ON" };
+    reply(bot, chat_id, &format!(
+        "🛡 <b>Anti-raid {}</b>{}\n\n\
+        For the next <b>{}</b> new members are <b>{}</b> and join requests are paused.\n\
+        <i>End it early with</i> <code>/antiraid off</code>{}",
+        verb, why, duration::fmt(secs), action_label(p.action), ping_admins(bot, chat_id, admins).await)).await;
+}
+
+/// Switches raid mode off, reports the tally and replays parked join
+/// requests. Called by /antiraid off and by the scheduler.
+pub async fn end(bot: &Bot, chat_id: i64, by: i64, why: &str, db: &Db) {
+    let mut caught = 0;
+    let mut was_on = false;
+    settings::update(db, chat_id, |s| {
+        was_on = s.raid.until.is_some();
+        caught = s.raid.caught;
+        s.raid.until = None;
+        s.raid.caught = 0;
+    });
+    if !was_on { return; }
+
+    let tally = format!("{} join{} blocked", caught, if caught == 1 { "" } else { "s" });
+    audit::record(bot, db, Entry::new(chat_id, by, Action::RaidOff).reason(&format!("{}; {}", why, tally))).await;
+    let resumed = join_requests::resume(bot, chat_id, db).await;
+    let resumed = if resumed > 0 { format!("\n{} paused join request(s) are being processed now.", resumed) } else { String::new() };
+    reply(bot, chat_id, &format!("🏳️ <b>Anti-raid OFF</b> — {}.\n\n{}.{}", html::escape(why), tally, resumed)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Joins — returns true if the member was stopped, in which case
+//  captcha and welcome are skipped.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn on_join(bot: &Bot, chat_id: i64, user: &User, db: &Db, flood: &FloodTracker, sched: &Scheduler, admins: &AdminCache) -> bool {
+    if user.id == bot.me.id { return false; }
+    let now = duration::now();
+    let mut p = settings::load(db, chat_id).raid;
+    let window = Duration::from_secs(p.auto_window.max(1) as u64);
+
+    let Some(burst) = flood.join(chat_id, user.id, p.auto_joins as usize, window) else {
+        // Second report of the same join; it was handled the first time.
+        return p.active(now);
+    };
+    if burst && !p.active(now) {
+        let trigger = format!("{} joins within {}", p.auto_joins, duration::fmt(p.auto_window));
+        start(bot, chat_id, bot.me.id, p.duration, Some(&trigger), db, sched, admins).await;
+        p = settings::load(db, chat_id).raid;
+    }
+    let Some(until) = p.until.filter(|u| *u > now) else { return false };
+    if approvals::is_approved(db, chat_id, user.id) { return false; }
+
+    let chat = ChatId::from(chat_id);
+    let done = match p.action {
+        RaidAction::Kick => match bot.ban_chat_member(chat.clone(), user.id, None).await {
+            Ok(_) => { let _ = bot.unban_chat_member(chat, user.id, None).await; true }
+            Err(e) => { log::warn!("antiraid: could not kick {} from {}: {}", user.id, chat_id, e); false }
+        },
+        RaidAction::Mute => {
+            let params = RestrictChatMemberParams::new().until_date(until);
+            match bot.restrict_chat_member(chat, user.id, no_perms(), Some(params)).await {
+                Ok(_) => true,
+                Err(e) => { log::warn!("antiraid: could not mute {} in {}: {}", user.id, chat_id, e); false }
+            }
+        }
+    };
+    if done {
+        settings::update(db, chat_id, |s| s.raid.caught += 1);
+    }
+    done
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /antiraid [duration|off]
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_antiraid(bot: &Bot, chat_id: i64, by: i64, arg: Option<&str>, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    let p = settings::load(db, chat_id).raid;
+    let now = duration::now();
+    let secs = match arg.map(str::to_lowercase).as_deref() {
+        Some("off") => {
+            if !p.active(now) {
+                reply(bot, chat_id, "ℹ️ Anti-raid is not on.").await;
+                return;
+            }
+            sched.cancel(chat_id, &Job::RaidEnd);
+            end(bot, chat_id, by, "switched off by an admin", db).await;
+            return;
+        }
+        None if p.active(now) => {
+            reply(bot, chat_id, &format!(
+                "🛡 Anti-raid is <b>on</b> for another <b>{}</b> · {} joins blocked so far.\n\n\
+                <i>Use</i> <code>/antiraid off</code> <i>to end it or</i> <code>/antiraid 2h</code> <i>to change the time left.</i>",
+                duration::fmt(p.until.unwrap_or(now) - now), p.caught)).await;
+            return;
+        }
+        None => p.duration,
+        Some(d) => match duration::parse(d) {
+            Ok(s) if s <= MAX_DURATION => s,
+            Ok(_) => {
+                reply(bot, chat_id, &format!("⚠️ Anti-raid can run for at most {}.", duration::fmt(MAX_DURATION))).await;
+                return;
+            }
+            Err(e) => {
+                reply(bot, chat_id, &format!(
+                    "⚠️ <code>{}</code>: {}\n\n<b>Usage:</b> <code>/antiraid [duration|off]</code>",
+                    html::escape(d), e)).await;
+                return;
+            }
+        },
+    };
+    start(bot, chat_id, by, secs, None, db, sched, admins).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /raidmode <kick|mute>   /autoraid <joins> [window]|off
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_raid_mode(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let action = match arg.map(str::to_lowercase).as_deref() {
+        Some("kick") => RaidAction::Kick,
+        Some("mute") => RaidAction::Mute,
+        Some(_) => {
+            reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/raidmode kick|mute</code>").await;
+            return;
+        }
+        None => {
+            let p = settings::load(db, chat_id).raid;
+            reply(bot, chat_id, &format!(
+                "🛡 During a raid new members are <b>{}</b>.\n\
+                Automatic trigger: <b>{}</b> · Default length: <b>{}</b>\n\n\
+                <i>Options:</i> <code>/raidmode kick</code> · <code>/raidmode mute</code>",
+                action_label(p.action), auto_label(&p), duration::fmt(p.duration))).await;
+            return;
+        }
+    };
+    settings::update(db, chat_id, |s| s.raid.action = action);
+    reply(bot, chat_id, &format!("✅ During a raid new members are now <b>{}</b>.", action_label(action))).await;
+}
+
+pub async fn handle_auto_raid(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    const USAGE: &str = "⚠️ <b>Usage:</b> <code>/autoraid &lt;joins&gt; [window]</code> or <code>/autoraid off</code>\n\
+        <i>Example:</i> <code>/autoraid 10 1m</code>";
+    let joins = match args.first().map(|a| a.to_lowercase()).as_deref() {
+        None => { reply(bot, chat_id, USAGE).await; return; }
+        Some("off" | "0") => 0,
+        Some(n) => match n.parse::<u32>() {
+            Ok(n) if (MIN_AUTO_JOINS..=MAX_AUTO_JOINS).contains(&n) => n,
+            _ => {
+                reply(bot, chat_id, &format!("⚠️ The join count must be from {} to {}.", MIN_AUTO_JOINS, MAX_AUTO_JOINS)).await;
+                return;
+            }
+        },
+    };
+    let window = match args.get(1) {
+        None => None,
+        Some(w) => match duration::parse(w) {
+            Ok(secs) if secs as u64 <= MAX_WINDOW.as_secs() => Some(secs),
+            Ok(_) => {
+                reply(bot, chat_id, &format!("⚠️ The window can be at most {}.", duration::fmt(MAX_WINDOW.as_secs() as i64))).await;
+                return;
+            }
+            Err(e) => {
+                reply(bot, chat_id, &format!("⚠️ <code>{}</code>: {}\n\n{}", html::escape(w), e, USAGE)).await;
+                return;
+            }
+        },
+    };
+    let p = settings::update(db, chat_id, |s| {
+        s.raid.auto_joins = joins;
+        if let Some(w) = window { s.raid.auto_window = w; }
+    }).raid;
+    reply(bot, chat_id, &format!("✅ Automatic anti-raid: <b>{}</b>.", auto_label(&p))).await;
+}
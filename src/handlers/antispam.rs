@@ -0,0 +1,240 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Antispam
+//  A new member's first few messages are scored by spam.rs. The
+//  chat's thresholds turn the score into delete, mute or ban; the
+//  message is deleted in every case. Members count as new for a day
+//  after joining. Admins and approved users are never scored.
+//  /antispam [on|off]   /spamlimits <delete> <mute> <ban>
+//  /spamscore (reply)
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    types::{Message, MessageEntity, MessageOrigin, User},
+    Bot, ChatId,
+};
+
+use super::approvals;
+use super::audit::{self, Action, Entry};
+use super::locks::entity_text;
+use super::warns;
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::html::{self, reply};
+use crate::scheduler::Scheduler;
+use crate::settings::{self, SpamPolicy, WarnAction};
+use crate::spam::{self, Post, Score, Verdict};
+use crate::storage::{chat_key, chat_prefix, Db, NEWCOMERS};
+use crate::targets::Target;
+
+/// Messages scored per new member.
+const SCORED_MESSAGES: usize = 3;
+/// How long after joining a member still counts as new.
+const NEWCOMER_TTL: i64 = duration::DAY;
+const MAX_THRESHOLD: u32 = 200;
+
+/// A member still being watched, keyed by `chat_key(chat, user)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Newcomer {
+    joined: i64,
+    /// Messages scored so far, normalized for the repeated-text signal.
+    #[serde(default)]
+    earlier: Vec<String>,
+}
+
+fn describe(p: &SpamPolicy) -> String {
+    let step = |at: u32| if at == 0 { "off".to_string() } else { at.to_string() };
+    format!("delete at <b>{}</b> · mute at <b>{}</b> · ban at <b>{}</b>", step(p.delete), step(p.mute), step(p.ban))
+}
+
+fn full_name(user: &User) -> String {
+    match &user.last_name {
+        Some(last) => format!("{} {}", user.first_name, last),
+        None => user.first_name.clone(),
+    }
+}
+
+/// Text or caption with its entities.
+fn body(msg: &Message) -> (&str, &[MessageEntity]) {
+    match (&msg.text, &msg.caption) {
+        (Some(t), _) => (t, msg.entities.as_deref().unwrap_or(&[])),
+        (_, Some(c)) => (c, msg.caption_entities.as_deref().unwrap_or(&[])),
+        _ => ("", &[]),
+    }
+}
+
+/// Scores `msg` as if sent `since_join` seconds after joining.
+fn score_message(msg: &Message, since_join: i64, earlier: &[String]) -> Score {
+    let (text, entities) = body(msg);
+    let links: Vec<String> = entities.iter().filter_map(|e| match e.r#type.as_str() {
+        "url"       => Some(entity_text(text, e)),
+        "text_link" => e.url.clone(),
+        _ => None,
+    }).collect();
+    let name = msg.from.as_deref().map(full_name).unwrap_or_default();
+    spam::score(&Post {
+        since_join,
+        text,
+        links: &links,
+        channel_forward: matches!(msg.forward_origin.as_deref(), Some(MessageOrigin::MessageOriginChannel(_))),
+        name: &name,
+        earlier,
+    })
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Joins and leaves — who counts as new
+// ════════════════════════════════════════════════════════════════
+
+pub fn on_join(db: &Db, chat_id: i64, user: &User) {
+    if user.is_bot || !settings::load(db, chat_id).spam.enabled { return; }
+    if approvals::is_approved(db, chat_id, user.id) { return; }
+    let now = duration::now();
+    for (key, n) in db.load_all::<Newcomer>(NEWCOMERS, &chat_prefix(chat_id)) {
+        if now - n.joined > NEWCOMER_TTL { db.delete(NEWCOMERS, &key); }
+    }
+    // A join is reported twice; the first report sets the time.
+    db.modify::<Newcomer, _>(NEWCOMERS, &chat_key(chat_id, user.id), |old| {
+        old.or(Some(Newcomer { joined: now, earlier: Vec::new() }))
+    });
+}
+
+pub fn on_leave(db: &Db, chat_id: i64, user_id: i64) {
+    db.delete(NEWCOMERS, &chat_key(chat_id, user_id));
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Enforcement — called for every group message
+//  Returns true if the message was removed.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn check(bot: &Bot, msg: &Message, db: &Db, sched: &Scheduler, admins: &AdminCache) -> bool {
+    let chat_id = msg.chat.id;
+    let policy = settings::load(db, chat_id).spam;
+    if !policy.enabled || msg.new_chat_members.is_some() || msg.left_chat_member.is_some() { return false; }
+    if msg.is_automatic_forward == Some(true) || msg.sender_chat.is_some() { return false; }
+    let Some(from) = &msg.from else { return false };
+    let key = chat_key(chat_id, from.id);
+    let Some(mut n) = db.load::<Newcomer>(NEWCOMERS, &key) else { return false };
+
+    let now = duration::now();
+    if now - n.joined > NEWCOMER_TTL {
+        db.delete(NEWCOMERS, &key);
+        return false;
+    }
+    let score = score_message(msg, now - n.joined, &n.earlier);
+    n.earlier.push(spam::normalize(body(msg).0));
+    if n.earlier.len() >= SCORED_MESSAGES { db.delete(NEWCOMERS, &key); } else { db.save(NEWCOMERS, &key, &n); }
+
+    let Some(verdict) = spam::verdict(score.total, &policy) else { return false };
+    if approvals::exempt(bot, chat_id, from.id, db, admins).await {
+        db.delete(NEWCOMERS, &key);
+        return false;
+    }
+
+    if let Err(e) = bot.delete_message(ChatId::from(chat_id), msg.message_id).await {
+        log::warn!("antispam: could not delete {} in {}: {}", msg.message_id, chat_id, e);
+    }
+    let t = Target { id: from.id, name: from.first_name.clone() };
+    let me = bot.me.id;
+    let reason = format!("spam score {}: {}", score.total, score.describe());
+    audit::record(bot, db, Entry::new(chat_id, me, Action::Spam).target(&t).reason(&reason)).await;
+
+    let action = match verdict {
+        Verdict::Delete => return true,
+        Verdict::Mute   => WarnAction::Mute { secs: None },
+        Verdict::Ban    => WarnAction::Ban,
+    };
+    db.delete(NEWCOMERS, &key);
+    match warns::punish(bot, chat_id, me, &t, action, &reason, db, sched).await {
+        Ok(_) => reply(bot, chat_id, &format!(
+            "🧪 <a href=\"tg://user?id={}\">{}</a> looks like a spammer (score {}) — <b>{}</b>.\n<i>{}</i>",
+            t.id, html::escape(&t.name), score.total, warns::action_label(action), html::escape(&score.describe()))).await,
+        Err(e) => log::warn!("antispam: {} on {} in {} failed: {}", warns::action_label(action), t.id, chat_id, e),
+    }
+    true
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /antispam [on|off]   /spamlimits <delete> <mute> <ban>
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_antispam(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let on = match arg.map(str::to_lowercase).as_deref() {
+        Some("on" | "yes") => true,
+        Some("off" | "no") => false,
+        Some(_) => {
+            reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/antispam on|off</code>").await;
+            return;
+        }
+        None => {
+            let p = settings::load(db, chat_id).spam;
+            reply(bot, chat_id, &format!(
+                "🧪 Spam scoring: <b>{}</b>\n{}\n\n\
+                <i>New members' first {} messages are scored. Change with</i> <code>/antispam on|off</code> \
+                <i>and</i> <code>/spamlimits 40 60 90</code>",
+                if p.enabled { "on" } else { "off" }, describe(&p), SCORED_MESSAGES)).await;
+            return;
+        }
+    };
+    let p = settings::update(db, chat_id, |s| s.spam.enabled = on).spam;
+    if on {
+        reply(bot, chat_id, &format!(
+            "✅ Spam scoring is <b>on</b>: {}.\n\n<i>Members who join from now on are scored.</i>", describe(&p))).await;
+    } else {
+        reply(bot, chat_id, "✅ Spam scoring is <b>off</b>.").await;
+    }
+}
+
+pub async fn handle_spam_limits(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    const USAGE: &str = "⚠️ <b>Usage:</b> <code>/spamlimits &lt;delete&gt; &lt;mute&gt; &lt;ban&gt;</code>\n\
+        <i>Example:</i> <code>/spamlimits 40 60 90</code> · <code>0</code> <i>switches a step off</i>";
+    let parsed: Vec<u32> = args.iter().filter_map(|a| a.parse::<u32>().ok()).collect();
+    let [delete, mute, ban] = parsed[..] else {
+        reply(bot, chat_id, USAGE).await;
+        return;
+    };
+    if args.len() != 3 || [delete, mute, ban].iter().any(|t| *t > MAX_THRESHOLD) {
+        reply(bot, chat_id, &format!("{}\n\nEach score must be from 0 to {}.", USAGE, MAX_THRESHOLD)).await;
+        return;
+    }
+    let steps: Vec<u32> = [delete, mute, ban].into_iter().filter(|t| *t > 0).collect();
+    if steps.windows(2).any(|w| w[0] > w[1]) {
+        reply(bot, chat_id, "⚠️ Harsher steps need higher scores: delete ≤ mute ≤ ban.").await;
+        return;
+    }
+    let p = settings::update(db, chat_id, |s| {
+        s.spam.delete = delete;
+        s.spam.mute = mute;
+        s.spam.ban = ban;
+    }).spam;
+    reply(bot, chat_id, &format!("✅ Spam thresholds: {}.", describe(&p))).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /spamscore — reply to a message to see how it scores
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_spam_score(bot: &Bot, chat_id: i64, target: Option<&Message>, db: &Db) {
+    let Some(msg) = target else {
+        reply(bot, chat_id, "⚠️ Reply to a message with <code>/spamscore</code> to see how it scores.").await;
+        return;
+    };
+    // Join time is only known for members still being watched.
+    let joined = msg.from.as_ref()
+        .and_then(|u| db.load::<Newcomer>(NEWCOMERS, &chat_key(chat_id, u.id)))
+        .map(|n| n.joined);
+    let score = score_message(msg, joined.map_or(i64::MAX, |j| msg.date - j), &[]);
+    let p = settings::load(db, chat_id).spam;
+    let outcome = match spam::verdict(score.total, &p) {
+        None                  => "nothing",
+        Some(Verdict::Delete) => "delete",
+        Some(Verdict::Mute)   => "delete and mute",
+        Some(Verdict::Ban)    => "delete and ban",
+    };
+    let hits: String = score.hits.iter().map(|(s, pts)| format!("\n• {} <b>+{}</b>", s.label(), pts)).collect();
+    let join = if joined.is_some() { "" } else { "\n\n<i>Join time unknown, so it doesn't count.</i>" };
+    reply(bot, chat_id, &format!(
+        "🧪 <b>Spam score: {}</b>{}\n\nFrom a new member this would mean: <b>{}</b>.{}",
+        score.total, hits, outcome, join)).await;
+}
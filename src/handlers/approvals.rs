@@ -0,0 +1,89 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Approved Users
+//  A per-chat allowlist of trusted members. Automated enforcement
+//  (locks, blocklist, antiflood, anti-raid, captcha) skips them, as
+//  it skips admins; admins always count as approved.
+//  /approve <user>   /unapprove <user>   /approved
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::Bot;
+
+use super::audit::{self, Action, Entry};
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::html::{self, reply};
+use crate::storage::{chat_key, chat_prefix, Db, APPROVED};
+use crate::targets::{self, Resolved};
+
+/// An allowlist entry, keyed by `chat_key(chat, user)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Approval {
+    name: String,
+    by: i64,
+    at: i64,
+}
+
+pub fn is_approved(db: &Db, chat_id: i64, user_id: i64) -> bool {
+    db.get(APPROVED, &chat_key(chat_id, user_id)).is_some()
+}
+
+/// Whether automated enforcement should leave `user_id` alone: admins
+/// and approved users. The cheap storage check runs first.
+pub async fn exempt(bot: &Bot, chat_id: i64, user_id: i64, db: &Db, admins: &AdminCache) -> bool {
+    is_approved(db, chat_id, user_id) || admins.lookup(bot, chat_id, user_id).await.is_ok_and(|m| m.is_admin())
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /approve <user>   /unapprove <user>
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_approve(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/approve", "").await else { return };
+    let who = format!("<a href=\"tg://user?id={}\">{}</a>", t.id, html::escape(&t.name));
+    if t.id == bot.me.id { return; }
+    if admins.lookup(bot, chat_id, t.id).await.is_ok_and(|m| m.is_admin()) {
+        reply(bot, chat_id, &format!("ℹ️ {} is an admin and is always exempt.", who)).await;
+        return;
+    }
+    let key = chat_key(chat_id, t.id);
+    if db.get(APPROVED, &key).is_some() {
+        reply(bot, chat_id, &format!("ℹ️ {} is already approved.", who)).await;
+        return;
+    }
+    db.save(APPROVED, &key, &Approval { name: t.name.clone(), by, at: duration::now() });
+    audit::record(bot, db, Entry::new(chat_id, by, Action::Approve).target(t)).await;
+    reply(bot, chat_id, &format!(
+        "✅ {} is <b>approved</b>.\n\n<i>Locks, the blocklist, antiflood, anti-raid and the captcha no longer apply to them.</i>",
+        who)).await;
+}
+
+pub async fn handle_unapprove(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db) {
+    let Some(t) = targets::require(bot, chat_id, target, "/unapprove", "").await else { return };
+    let who = format!("<a href=\"tg://user?id={}\">{}</a>", t.id, html::escape(&t.name));
+    if !db.delete(APPROVED, &chat_key(chat_id, t.id)) {
+        reply(bot, chat_id, &format!("❓ {} isn't approved.", who)).await;
+        return;
+    }
+    audit::record(bot, db, Entry::new(chat_id, by, Action::Unapprove).target(t)).await;
+    reply(bot, chat_id, &format!("↩️ {} is no longer approved.", who)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /approved — the allowlist
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_approved(bot: &Bot, chat_id: i64, db: &Db) {
+    let prefix = chat_prefix(chat_id);
+    let list = db.load_all::<Approval>(APPROVED, &prefix);
+    if list.is_empty() {
+        reply(bot, chat_id, "✅ <b>Approved users</b>\n\n<i>None yet. Add with</i> <code>/approve &lt;user&gt;</code>").await;
+        return;
+    }
+    let now = duration::now();
+    let lines: String = list.iter()
+        .map(|(k, a)| format!("\n• <a href=\"tg://user?id={}\">{}</a> · {} ago",
+            &k[prefix.len()..], html::escape(&a.name), duration::age(now - a.at)))
+        .collect();
+    reply(bot, chat_id, &format!("✅ <b>Approved users</b> ({}){}\n\n<i>Admins are always exempt.</i>", list.len(), lines)).await;
+}
@@ -0,0 +1,327 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Moderation Audit Log
+//  Every moderation action is stored as an `Entry` in the `audit`
+//  table and, if the chat has one, posted to its log channel.
+//  /setlog <channel|off>   /modlog [user]
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{gen_methods::SendMessageParams, Bot};
+
+use crate::duration;
+use crate::html::{self, reply};
+use crate::permissions;
+use crate::settings::{self, JoinMode};
+use crate::storage::{chat_key, chat_prefix, Db, AUDIT, CHATS};
+use crate::targets::{self, Lookup, Resolved, Target};
+
+/// Entries kept per chat; older ones are pruned on insert.
+const MAX_ENTRIES: usize = 1000;
+const MODLOG_PAGE: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Ban,
+    Unban,
+    Kick,
+    Mute,
+    Unmute,
+    Warn,
+    Unwarn,
+    ResetWarns,
+    Promote,
+    Demote,
+    Title,
+    Pin,
+    Unpin,
+    Delete,
+    ReadOnly,
+    Unro,
+    AcceptJoin,
+    DeclineJoin,
+    Blocklist,
+    RaidOn,
+    RaidOff,
+    Restrict,
+    Approve,
+    Unapprove,
+    Spam,
+}
+
+impl Action {
+    fn emoji(self) -> &'static str {
+        match self {
+            Action::Ban         => "🔨",
+            Action::Unban       => "✅",
+            Action::Kick        => "👢",
+            Action::Mute        => "🔇",
+            Action::Unmute      => "🔊",
+            Action::Warn        => "⚠️",
+            Action::Unwarn      => "↩️",
+            Action::ResetWarns  => "🧹",
+            Action::Promote     => "⭐",
+            Action::Demote      => "🔽",
+            Action::Title       => "🏷️",
+            Action::Pin         => "📌",
+            Action::Unpin       => "📍",
+            Action::Delete      => "🗑",
+            Action::ReadOnly    => "🔒",
+            Action::Unro        => "🔓",
+            Action::AcceptJoin  => "🚪",
+            Action::DeclineJoin => "⛔",
+            Action::Blocklist   => "🚫",
+            Action::RaidOn      => "🛡",
+            Action::RaidOff     => "🏳️",
+            Action::Restrict    => "⛓",
+            Action::Approve     => "👍",
+            Action::Unapprove   => "👎",
+            Action::Spam        => "🧪",
+        }
+    }
+
+    /// Hashtag used in the log channel, e.g. `#BAN`.
+    fn tag(self) -> &'static str {
+        match self {
+            Action::Ban         => "BAN",
+            Action::Unban       => "UNBAN",
+            Action::Kick        => "KICK",
+            Action::Mute        => "MUTE",
+            Action::Unmute      => "UNMUTE",
+            Action::Warn        => "WARN",
+            Action::Unwarn      => "UNWARN",
+            Action::ResetWarns  => "RESETWARNS",
+            Action::Promote     => "PROMOTE",
+            Action::Demote      => "DEMOTE",
+            Action::Title       => "TITLE",
+            Action::Pin         => "PIN",
+            Action::Unpin       => "UNPIN",
+            Action::Delete      => "DELETE",
+            Action::ReadOnly    => "READONLY",
+            Action::Unro        => "UNRO",
+            Action::AcceptJoin  => "JOIN_ACCEPTED",
+            Action::DeclineJoin => "JOIN_DECLINED",
+            Action::Blocklist   => "BLOCKLIST",
+            Action::RaidOn      => "ANTIRAID_ON",
+            Action::RaidOff     => "ANTIRAID_OFF",
+            Action::Restrict    => "RESTRICT",
+            Action::Approve     => "APPROVE",
+            Action::Unapprove   => "UNAPPROVE",
+            Action::Spam        => "SPAM",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub chat_id: i64,
+    pub actor: i64,
+    pub action: Action,
+    #[serde(default)]
+    pub target: Option<i64>,
+    #[serde(default)]
+    pub target_name: Option<String>,
+    /// Seconds; `None` = permanent or not applicable.
+    #[serde(default)]
+    pub duration: Option<i64>,
+    #[serde(default)]
+    pub reason: String,
+    pub at: i64,
+}
+
+impl Entry {
+    pub fn new(chat_id: i64, actor: i64, action: Action) -> Self {
+        Entry {
+            chat_id, actor, action,
+            target: None, target_name: None, duration: None,
+            reason: String::new(), at: duration::now(),
+        }
+    }
+
+    pub fn target(mut self, t: &Target) -> Self {
+        self.target = Some(t.id);
+        self.target_name = Some(t.name.clone());
+        self
+    }
+
+    pub fn duration(mut self, secs: Option<i64>) -> Self {
+        self.duration = secs;
+        self
+    }
+
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = reason.to_string();
+        self
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  record — store the entry and copy it to the log channel
+// ════════════════════════════════════════════════════════════════
+
+pub async fn record(bot: &Bot, db: &Db, entry: Entry) {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    db.save(AUDIT, &chat_key(entry.chat_id, format!("{:020}", stamp)), &entry);
+    prune(db, entry.chat_id);
+
+    if let Some(channel) = settings::load(db, entry.chat_id).log_channel {
+        let p = SendMessageParams::new().parse_mode("HTML");
+        if let Err(e) = bot.send_message(channel, channel_text(db, &entry), Some(p)).await {
+            log::warn!("audit: could not post to log channel {} for {}: {}", channel, entry.chat_id, e);
+        }
+    }
+}
+
+fn prune(db: &Db, chat_id: i64) {
+    let keys = db.scan(AUDIT, &chat_prefix(chat_id));
+    if keys.len() > MAX_ENTRIES {
+        for (k, _) in &keys[..keys.len() - MAX_ENTRIES] {
+            db.delete(AUDIT, k);
+        }
+    }
+}
+
+fn user_link(db: &Db, id: i64, name: Option<&str>) -> String {
+    let name = name.map(str::to_string)
+        .or_else(|| targets::known(db, id).map(|k| k.first_name))
+        .unwrap_or_else(|| id.to_string());
+    format!("<a href=\"tg://user?id={}\">{}</a>", id, html::escape(&name))
+}
+
+fn chat_title(db: &Db, chat_id: i64) -> String {
+    db.get(CHATS, &chat_id.to_string())
+        .and_then(|v| v.get("title").and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_else(|| chat_id.to_string())
+}
+
+fn channel_text(db: &Db, e: &Entry) -> String {
+    let mut text = format!(
+        "{} <b>#{}</b>\n<b>Chat:</b> {} (<code>{}</code>)",
+        e.action.emoji(), e.action.tag(), html::escape(&chat_title(db, e.chat_id)), e.chat_id);
+    if let Some(id) = e.target {
+        text.push_str(&format!("\n<b>User:</b> {} (<code>{}</code>)", user_link(db, id, e.target_name.as_deref()), id));
+    }
+    text.push_str(&format!("\n<b>By:</b> {}", user_link(db, e.actor, None)));
+    if let Some(secs) = e.duration {
+        text.push_str(&format!("\n<b>For:</b> {}", duration::fmt(secs)));
+    }
+    if !e.reason.is_empty() {
+        text.push_str(&format!("\n<b>Reason:</b> {}", html::escape(&e.reason)));
+    }
+    text
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /setlog <channel id | @channel | off>
+//  The bot must be able to post there; a test message confirms it.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_set_log(bot: &Bot, chat_id: i64, by: i64, arg: Option<&str>, db: &Db) {
+    let Some(a) = arg else {
+        let text = match settings::load(db, chat_id).log_channel {
+            Some(id) => format!("📋 Moderation log goes to <code>{}</code>.\n\n<i>Disable with</i> <code>/setlog off</code>", id),
+            None => "📋 No log channel set.\n\n\
+                <b>Usage:</b> <code>/setlog -1001234567890</code> or <code>/setlog @channel</code>\n\
+                <i>Add the bot to the channel as an admin first.</i>".to_string(),
+        };
+        reply(bot, chat_id, &text).await;
+        return;
+    };
+
+    if a.eq_ignore_ascii_case("off") {
+        let s = settings::update(db, chat_id, |s| s.log_channel = None);
+        let note = if s.join.mode == JoinMode::Review {
+            "\n\n<i>Join requests can't be reviewed here without it; they now wait in Telegram's pending list.</i>"
+        } else { "" };
+        reply(bot, chat_id, &format!("✅ Log channel removed.{}", note)).await;
+        return;
+    }
+
+    let channel = match a.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => match bot.get_chat(a).await {
+            Ok(info) => info.id,
+            Err(e) => {
+                reply(bot, chat_id, &format!("❌ Could not find <code>{}</code>: <code>{}</code>", html::escape(a), e)).await;
+                return;
+            }
+        },
+    };
+
+    // Entries name users, so only the channel's own admins may send them there.
+    match permissions::fetch_member(bot, channel, by).await {
+        Ok(m) if m.is_admin() => {}
+        Ok(_) => {
+            reply(bot, chat_id, &format!(
+                "🚫 You must be an admin of <code>{}</code> to send the moderation log there.", channel)).await;
+            return;
+        }
+        Err(e) => {
+            reply(bot, chat_id, &format!(
+                "❌ Could not check your rights in <code>{}</code>: <code>{}</code>\n\
+                <i>Add me to the channel as an admin first.</i>",
+                channel, e)).await;
+            return;
+        }
+    }
+
+    let p = SendMessageParams::new().parse_mode("HTML");
+    let hello = format!("📋 This channel now receives the moderation log for <b>{}</b>.",
+        html::escape(&chat_title(db, chat_id)));
+    match bot.send_message(channel, hello, Some(p)).await {
+        Ok(_) => {
+            settings::update(db, chat_id, |s| s.log_channel = Some(channel));
+            reply(bot, chat_id, &format!("✅ Moderation log will be posted to <code>{}</code>.", channel)).await;
+        }
+        Err(e) => reply(bot, chat_id, &format!(
+            "❌ I can't post in <code>{}</code>: <code>{}</code>\n<i>Add me to the channel as an admin first.</i>",
+            channel, e)).await,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /modlog [user] — Recent actions in this chat, newest first
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_modlog(bot: &Bot, chat_id: i64, target: &Resolved, db: &Db) {
+    let filter = match &target.target {
+        Lookup::Missing => None,
+        _ => match targets::require(bot, chat_id, target, "/modlog", "").await {
+            Some(t) => Some(t.id),
+            None => return,
+        },
+    };
+
+    let entries: Vec<Entry> = db.load_all::<Entry>(AUDIT, &chat_prefix(chat_id))
+        .into_iter()
+        .rev()
+        .map(|(_, e)| e)
+        .filter(|e| filter.is_none() || e.target == filter)
+        .take(MODLOG_PAGE)
+        .collect();
+
+    if entries.is_empty() {
+        reply(bot, chat_id, "📋 <b>Mod log</b>\n\n<i>No actions recorded yet.</i>").await;
+        return;
+    }
+
+    let now = duration::now();
+    let mut text = String::from("📋 <b>Mod log</b> <i>(newest first)</i>");
+    for e in &entries {
+        text.push_str(&format!("\n\n{} <b>{}</b>", e.action.emoji(), e.action.tag().to_lowercase()));
+        if let Some(id) = e.target {
+            text.push_str(&format!(" · {}", user_link(db, id, e.target_name.as_deref())));
+        }
+        text.push_str(&format!("\n   <i>by</i> {} · {} ago", user_link(db, e.actor, None), duration::age(now - e.at)));
+        if let Some(secs) = e.duration {
+            text.push_str(&format!(" · {}", duration::fmt(secs)));
+        }
+        if !e.reason.is_empty() {
+            text.push_str(&format!("\n   {}", html::escape(&e.reason)));
+        }
+    }
+    reply(bot, chat_id, &text).await;
+}
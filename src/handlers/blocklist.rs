@@ -0,0 +1,229 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Blocklist
+//  Words and patterns that get a message deleted and, depending on
+//  the chat's /blockmode, the sender warned, muted, kicked or banned.
+//  Checked on new and edited messages, text and captions alike.
+//  /addblock <pattern>   /rmblock <pattern>   /blocklist
+//  /blockmode <delete|warn|mute [d]|kick|ban|tban d>
+//
+//  ── Pattern syntax ─────────────────────────────────────────────
+//    casino          whole word, any case
+//    free money      whole phrase
+//    *coin           `*` matches any characters within a word
+//    *t.me/*         …so links can be caught by part
+//  ───────────────────────────────────────────────────────────────
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::{types::Message, Bot, ChatId};
+
+use super::approvals;
+use super::audit::{self, Action, Entry};
+use super::warns;
+use crate::admin_cache::AdminCache;
+use crate::html::{self, reply};
+use crate::scheduler::Scheduler;
+use crate::settings::{self, BlocklistAction};
+use crate::storage::{chat_key, chat_prefix, Db, BLOCKLIST};
+use crate::targets::Target;
+
+const MAX_PATTERNS: usize = 150;
+const MAX_PATTERN_LEN: usize = 100;
+
+// ── Matching ─────────────────────────────────────────────────────────────────
+
+/// Whether lowercased `text` contains `pattern` (see the header for syntax).
+fn matches(text: &str, pattern: &str) -> bool {
+    if pattern.contains('*') {
+        if pattern.contains(char::is_whitespace) {
+            return glob(text, &format!("*{}*", pattern));
+        }
+        return text.split_whitespace().any(|word| {
+            let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+            glob(word, pattern) || glob(bare, pattern)
+        });
+    }
+    // Whole words only: the characters around a hit must not be alphanumeric.
+    let mut from = 0;
+    while let Some(pos) = text[from..].find(pattern) {
+        let start = from + pos;
+        let end = start + pattern.len();
+        let before = text[..start].chars().next_back().is_none_or(|c| !c.is_alphanumeric());
+        let after = text[end..].chars().next().is_none_or(|c| !c.is_alphanumeric());
+        if before && after { return true; }
+        from = start + text[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    false
+}
+
+/// `*`-only glob, anchored at both ends.
+fn glob(text: &str, pattern: &str) -> bool {
+    let (t, p): (Vec<char>, Vec<char>) = (text.chars().collect(), pattern.chars().collect());
+    let (mut ti, mut pi) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && p[pi] != '*' && p[pi] == t[ti] {
+            ti += 1;
+            pi += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+fn patterns(db: &Db, chat_id: i64) -> Vec<String> {
+    let prefix = chat_prefix(chat_id);
+    db.scan(BLOCKLIST, &prefix).into_iter().map(|(k, _)| k[prefix.len()..].to_string()).collect()
+}
+
+pub fn action_label(action: BlocklistAction) -> String {
+    match action {
+        BlocklistAction::Delete    => "delete".into(),
+        BlocklistAction::Warn      => "delete + warn".into(),
+        BlocklistAction::Punish(a) => format!("delete + {}", warns::action_label(a)),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Enforcement — called for new and edited group messages
+//  Returns true if the message was blocklisted.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn check(bot: &Bot, msg: &Message, db: &Db, sched: &Scheduler, admins: &AdminCache) -> bool {
+    let chat_id = msg.chat.id;
+    let Some(text) = msg.text.as_deref().or(msg.caption.as_deref()) else { return false };
+    let lower = text.to_lowercase();
+    let Some(pattern) = patterns(db, chat_id).into_iter().find(|p| matches(&lower, p)) else { return false };
+
+    // Anonymous admins and the linked channel are exempt, like admins
+    // and approved users.
+    if msg.is_automatic_forward == Some(true) { return false; }
+    if msg.sender_chat.as_ref().is_some_and(|c| c.id == chat_id) { return false; }
+    let Some(from) = &msg.from else { return false };
+    if approvals::exempt(bot, chat_id, from.id, db, admins).await { return false; }
+
+    if let Err(e) = bot.delete_message(ChatId::from(chat_id), msg.message_id).await {
+        log::warn!("blocklist: could not delete {} in {}: {}", msg.message_id, chat_id, e);
+    }
+    let t = Target { id: from.id, name: from.first_name.clone() };
+    let me = bot.me.id;
+    let reason = format!("blocklisted: {}", pattern);
+    audit::record(bot, db, Entry::new(chat_id, me, Action::Blocklist).target(&t).reason(&pattern)).await;
+
+    match settings::load(db, chat_id).blocklist {
+        BlocklistAction::Delete => {}
+        BlocklistAction::Warn => warns::warn_user(bot, chat_id, me, &t, &reason, db, sched).await,
+        BlocklistAction::Punish(action) => {
+            let who = format!("<a href=\"tg://user?id={}\">{}</a>", t.id, html::escape(&t.name));
+            match warns::punish(bot, chat_id, me, &t, action, &reason, db, sched).await {
+                Ok(_) => reply(bot, chat_id, &format!(
+                    "🚫 {} used a blocklisted word — <b>{}</b>.", who, warns::action_label(action))).await,
+                Err(e) => log::warn!("blocklist: {} on {} in {} failed: {}", warns::action_label(action), t.id, chat_id, e),
+            }
+        }
+    }
+    true
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /addblock <pattern>  — one pattern per line
+//  /rmblock <pattern>
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_add(bot: &Bot, chat_id: i64, raw: &str, db: &Db) {
+    let wanted: Vec<String> = raw.lines()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty() && l.chars().any(|c| c != '*'))
+        .collect();
+    if wanted.is_empty() {
+        reply(bot, chat_id,
+            "⚠️ <b>Usage:</b> <code>/addblock pattern</code>\n\n\
+            One pattern per line. <code>*</code> matches any characters within a word:\n\
+            <pre>/addblock casino\nfree money\n*t.me/*</pre>").await;
+        return;
+    }
+    if let Some(long) = wanted.iter().find(|p| p.chars().count() > MAX_PATTERN_LEN) {
+        reply(bot, chat_id, &format!("⚠️ Pattern too long (max {} characters): <code>{}</code>",
+            MAX_PATTERN_LEN, html::escape(long))).await;
+        return;
+    }
+    let existing = patterns(db, chat_id);
+    let new: Vec<&String> = wanted.iter().filter(|p| !existing.contains(p)).collect();
+    if existing.len() + new.len() > MAX_PATTERNS {
+        reply(bot, chat_id, &format!("⚠️ A chat can have at most {} blocklist patterns.", MAX_PATTERNS)).await;
+        return;
+    }
+    for p in &new {
+        db.save(BLOCKLIST, &chat_key(chat_id, p), &true);
+    }
+    let list: Vec<String> = wanted.iter().map(|p| format!("<code>{}</code>", html::escape(p))).collect();
+    reply(bot, chat_id, &format!("🚫 <b>Blocklisted:</b> {}\n\n<i>Action:</i> {}",
+        list.join(", "), action_label(settings::load(db, chat_id).blocklist))).await;
+}
+
+pub async fn handle_remove(bot: &Bot, chat_id: i64, raw: &str, db: &Db) {
+    let pattern = raw.trim().to_lowercase();
+    if pattern.is_empty() {
+        reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/rmblock pattern</code>").await;
+        return;
+    }
+    if db.delete(BLOCKLIST, &chat_key(chat_id, &pattern)) {
+        reply(bot, chat_id, &format!("🗑️ Removed from the blocklist: <code>{}</code>", html::escape(&pattern))).await;
+    } else {
+        reply(bot, chat_id, &format!("❓ Not on the blocklist: <code>{}</code>", html::escape(&pattern))).await;
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /blocklist — patterns and action
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_list(bot: &Bot, chat_id: i64, db: &Db) {
+    let list = patterns(db, chat_id);
+    let action = action_label(settings::load(db, chat_id).blocklist);
+    if list.is_empty() {
+        reply(bot, chat_id, &format!(
+            "🚫 <b>Blocklist</b>\n\n<i>Empty. Add with</i> <code>/addblock pattern</code>\n<i>Action:</i> {}", action)).await;
+        return;
+    }
+    let lines: String = list.iter().map(|p| format!("\n• <code>{}</code>", html::escape(p))).collect();
+    reply(bot, chat_id, &format!("🚫 <b>Blocklist</b> ({} patterns){}\n\n<i>Action:</i> {}", list.len(), lines, action)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /blockmode <delete|warn|ban|tban d|kick|mute [d]>
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_mode(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    const OPTIONS: &str = "<code>delete</code> · <code>warn</code> · <code>mute [duration]</code> · \
+        <code>kick</code> · <code>ban</code> · <code>tban &lt;duration&gt;</code>";
+    let action = match args.first().map(|a| a.to_lowercase()).as_deref() {
+        None => {
+            let current = action_label(settings::load(db, chat_id).blocklist);
+            reply(bot, chat_id, &format!("🚫 Blocklist action: <b>{}</b>\n\n<i>Options:</i> {}", current, OPTIONS)).await;
+            return;
+        }
+        Some("delete") => BlocklistAction::Delete,
+        Some("warn") => BlocklistAction::Warn,
+        Some(_) => match warns::parse_action(args) {
+            Ok(a) => BlocklistAction::Punish(a),
+            Err(None) => {
+                reply(bot, chat_id, &format!("⚠️ <b>Usage:</b> <code>/blockmode &lt;action&gt;</code>\n\n<i>Options:</i> {}", OPTIONS)).await;
+                return;
+            }
+            Err(Some(why)) => {
+                reply(bot, chat_id, &format!("⚠️ <b>Invalid duration</b> {}", why)).await;
+                return;
+            }
+        },
+    };
+    settings::update(db, chat_id, |s| s.blocklist = action);
+    reply(bot, chat_id, &format!("✅ Blocklist action: <b>{}</b>.", action_label(action))).await;
+}
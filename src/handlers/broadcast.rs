@@ -60,7 +60,7 @@ fn parse_button_rows(lines: &[&str]) -> Vec<Vec<InlineKeyboardButton>> {
 }
 
 // Split raw text into (message_body, button_rows)
-fn split_body_buttons(raw: &str) -> (String, Vec<Vec<InlineKeyboardButton>>) {
+pub fn split_body_buttons(raw: &str) -> (String, Vec<Vec<InlineKeyboardButton>>) {
     let lines: Vec<&str> = raw.lines().collect();
     let (text_lines, btn_lines): (Vec<&str>, Vec<&str>) = lines.iter().partition(|l| {
         let t = l.trim();
@@ -10,7 +10,9 @@ use tgbotrs::{
 };
 
 use super::commands::*;
+use crate::admin_cache::AdminCache;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_callback(
     bot: &Bot,
     cq_id: String,
@@ -19,6 +21,7 @@ pub async fn handle_callback(
     data: &str,
     user_id: i64,
     first_name: &str,
+    admins: &AdminCache,
 ) {
     match data {
         // ── Navigation ──────────────────────────────────────────────────
@@ -39,6 +42,11 @@ pub async fn handle_callback(
             handle_about(bot, chat_id, Some(message_id)).await;
         }
 
+        "help_cb" => {
+            ack(bot, &cq_id, None, false).await;
+            handle_help(bot, chat_id).await;
+        }
+
         "library" => {
             ack(bot, &cq_id, None, false).await;
             handle_library(bot, chat_id, Some(message_id)).await;
@@ -80,7 +88,7 @@ pub async fn handle_callback(
         "bot_details" => { ack(bot, &cq_id, Some("🤖 Getting info..."), false).await; handle_bot_info(bot, chat_id, Some(message_id)).await; }
         "botinfo"     => { ack(bot, &cq_id, Some("🤖 Getting info..."), false).await; handle_bot_info(bot, chat_id, Some(message_id)).await; }
         "member_count" => { ack(bot, &cq_id, Some("👥 Counting..."), false).await; handle_member_count(bot, chat_id, Some(message_id)).await; }
-        "admins"      => { ack(bot, &cq_id, Some("👑 Fetching..."), false).await; handle_admins(bot, chat_id, Some(message_id)).await; }
+        "admins"      => { ack(bot, &cq_id, Some("👑 Fetching..."), false).await; handle_admins(bot, chat_id, Some(message_id), admins).await; }
         "invite_link" => { ack(bot, &cq_id, Some("🔗 Generating..."), false).await; handle_invite_link(bot, chat_id, Some(message_id)).await; }
         "my_commands" => { ack(bot, &cq_id, Some("📋 Fetching..."), false).await; handle_my_commands(bot, chat_id, Some(message_id)).await; }
         "my_profile"  => { ack(bot, &cq_id, Some("👤 Fetching..."), false).await; handle_my_profile(bot, chat_id, user_id).await; }
@@ -0,0 +1,366 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Join Captcha
+//  New members are muted and shown a challenge: a button, a small
+//  sum or a named emoji. Solving it lifts the mute; a timeout or
+//  too many wrong answers kicks them. The challenge message is
+//  deleted either way. Approved users skip it. A member who was
+//  already restricted (e.g. left and rejoined while muted) gets that
+//  restriction back instead of the chat defaults.
+//  /captcha [on|off|button|math|emoji]   /captchatime [duration]
+// ════════════════════════════════════════════════════════════════
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::{AnswerCallbackQueryParams, RestrictChatMemberParams, SendMessageParams},
+    types::{ChatPermissions, InlineKeyboardButton, InlineKeyboardMarkup, User},
+    Bot, ChatId, ReplyMarkup,
+};
+
+use super::approvals;
+use super::audit::{self, Action, Entry};
+use super::moderation::{default_perms, no_perms};
+use crate::duration::{self, DAY, MIN_RESTRICTION};
+use crate::html::{self, reply};
+use crate::permissions;
+use crate::scheduler::{Job, Scheduler};
+use crate::settings::{self, CaptchaMode};
+use crate::storage::{chat_key, Db, CAPTCHAS};
+use crate::targets::Target;
+
+const MAX_ATTEMPTS: u8 = 3;
+const MAX_TIMEOUT: i64 = DAY;
+
+const EMOJIS: &[(&str, &str)] = &[
+    ("🍎", "apple"), ("🚗", "car"), ("🐶", "dog"), ("🌵", "cactus"),
+    ("⚽", "football"), ("🎸", "guitar"), ("🍕", "pizza"), ("🚀", "rocket"),
+    ("🐢", "turtle"), ("☂️", "umbrella"), ("🔑", "key"), ("🦀", "crab"),
+];
+
+/// A pending challenge, keyed by `chat_key(chat, user)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Challenge {
+    name: String,
+    /// 0 until the challenge message is sent.
+    message_id: i64,
+    answer: String,
+    attempts: u8,
+    /// The member's restriction before the captcha, put back on success.
+    #[serde(default)]
+    prior: Option<Restriction>,
+}
+
+/// A restriction as found on the member when they joined.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Restriction {
+    perms: ChatPermissions,
+    /// Unix time it ends; 0 = never.
+    until: i64,
+}
+
+/// The restriction still in force on a `getChatMember` result, if any.
+fn prior_restriction(member: &serde_json::Value, now: i64) -> Option<Restriction> {
+    if member.get("status").and_then(|s| s.as_str()) != Some("restricted") { return None; }
+    let until = member.get("until_date").and_then(|u| u.as_i64()).unwrap_or(0);
+    if until != 0 && until <= now { return None; }
+    let perms = serde_json::from_value(member.clone()).ok()?;
+    Some(Restriction { perms, until })
+}
+
+async fn toast(bot: &Bot, cq_id: &str, text: &str) {
+    let p = AnswerCallbackQueryParams::new().text(text.to_string());
+    let _ = bot.answer_callback_query(cq_id, Some(p)).await;
+}
+
+// ── Challenges ───────────────────────────────────────────────────────────────
+//   Button data is `captcha:<user_id>:<choice>`; the right choice is stored.
+
+fn button(user_id: i64, text: &str, choice: &str) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text: text.to_string(),
+        callback_data: Some(format!("captcha:{}:{}", user_id, choice)),
+        ..Default::default()
+    }
+}
+
+/// Prompt, keyboard and correct choice for `mode`.
+fn challenge(mode: CaptchaMode, user_id: i64) -> (String, InlineKeyboardMarkup, String) {
+    let mut rng = rand::thread_rng();
+    match mode {
+        CaptchaMode::Button => (
+            "press the button below".to_string(),
+            InlineKeyboardMarkup { inline_keyboard: vec![vec![button(user_id, "✅ I'm human", "ok")]] },
+            "ok".to_string(),
+        ),
+        CaptchaMode::Math => {
+            let (a, b) = (rng.gen_range(2..=12), rng.gen_range(2..=12));
+            let answer: i64 = a + b;
+            let mut options = vec![answer];
+            while options.len() < 4 {
+                let wrong = answer + rng.gen_range(-5..=5);
+                if wrong > 0 && !options.contains(&wrong) { options.push(wrong); }
+            }
+            options.shuffle(&mut rng);
+            let row = options.iter().map(|n| button(user_id, &n.to_string(), &n.to_string())).collect();
+            (format!("tap the answer to <b>{} + {}</b>", a, b),
+             InlineKeyboardMarkup { inline_keyboard: vec![row] },
+             answer.to_string())
+        }
+        CaptchaMode::Emoji => {
+            let picks: Vec<usize> = rand::seq::index::sample(&mut rng, EMOJIS.len(), 6).into_vec();
+            let answer = picks[rng.gen_range(0..picks.len())];
+            let rows = picks.chunks(3)
+                .map(|c| c.iter().map(|&i| button(user_id, EMOJIS[i].0, &i.to_string())).collect())
+                .collect();
+            (format!("tap the <b>{}</b>", EMOJIS[answer].1),
+             InlineKeyboardMarkup { inline_keyboard: rows },
+             answer.to_string())
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Join — mute, post the challenge, schedule the deadline
+//  Called for `new_chat_members` and `chat_member` joins alike; the
+//  pending entry makes the second call a no-op.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn on_join(bot: &Bot, chat_id: i64, user: &User, db: &Db, sched: &Scheduler) {
+    let policy = settings::load(db, chat_id).captcha;
+    if !policy.enabled || user.is_bot || approvals::is_approved(db, chat_id, user.id) { return; }
+
+    let key = chat_key(chat_id, user.id);
+    if db.get(CAPTCHAS, &key).is_some() { return; }
+    let prior = match permissions::fetch_member(bot, chat_id, user.id).await {
+        Ok(m) => prior_restriction(m.raw(), duration::now()),
+        Err(e) => {
+            log::warn!("captcha: could not check {} in {}: {}", user.id, chat_id, e);
+            return;
+        }
+    };
+    let (text, kb, answer) = challenge(policy.mode, user.id);
+    let mut fresh = false;
+    db.modify::<Challenge, _>(CAPTCHAS, &key, |old| {
+        if old.is_some() { return old; }
+        fresh = true;
+        Some(Challenge { name: user.first_name.clone(), message_id: 0, answer, attempts: 0, prior })
+    });
+    if !fresh { return; }
+
+    if let Err(e) = bot.restrict_chat_member(ChatId::from(chat_id), user.id, no_perms(), None).await {
+        log::warn!("captcha: could not mute {} in {}: {}", user.id, chat_id, e);
+        db.delete(CAPTCHAS, &key);
+        return;
+    }
+
+    let text = format!(
+        "👋 Welcome, <a href=\"tg://user?id={}\">{}</a>!\n\n\
+        To prove you're human, {} within <b>{}</b>.",
+        user.id, html::escape(&user.first_name), text, duration::fmt(policy.timeout));
+    let p = SendMessageParams::new().parse_mode("HTML").reply_markup(ReplyMarkup::InlineKeyboard(kb));
+    match bot.send_message(chat_id, text, Some(p)).await {
+        Ok(sent) => {
+            db.modify::<Challenge, _>(CAPTCHAS, &key, |old| old.map(|c| Challenge { message_id: sent.message_id, ..c }));
+        }
+        Err(e) => log::warn!("captcha: could not post challenge in {}: {}", chat_id, e),
+    }
+    sched.schedule(chat_id, policy.timeout, Job::CaptchaTimeout { user_id: user.id, name: user.first_name.clone() });
+}
+
+/// Removes the pending challenge and its message; returns it if there was one.
+async fn clear(bot: &Bot, chat_id: i64, user_id: i64, db: &Db) -> Option<Challenge> {
+    let key = chat_key(chat_id, user_id);
+    let pending: Option<Challenge> = db.load(CAPTCHAS, &key);
+    if let Some(c) = &pending {
+        db.delete(CAPTCHAS, &key);
+        if c.message_id != 0 {
+            let _ = bot.delete_message(ChatId::from(chat_id), c.message_id).await;
+        }
+    }
+    pending
+}
+
+async fn kick(bot: &Bot, chat_id: i64, user_id: i64, name: String, db: &Db, reason: &str) {
+    let chat = ChatId::from(chat_id);
+    match bot.ban_chat_member(chat.clone(), user_id, None).await {
+        Ok(_) => {
+            let _ = bot.unban_chat_member(chat, user_id, None).await;
+            let t = Target { id: user_id, name };
+            audit::record(bot, db, Entry::new(chat_id, bot.me.id, Action::Kick).target(&t).reason(reason)).await;
+        }
+        Err(e) => log::warn!("captcha: could not kick {} from {}: {}", user_id, chat_id, e),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Answer — callback `captcha:<user_id>:<choice>`
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_answer(bot: &Bot, cq_id: &str, chat_id: i64, presser: i64, data: &str, db: &Db, sched: &Scheduler) {
+    let Some((uid, choice)) = data.split_once(':') else { return };
+    let Ok(user_id) = uid.parse::<i64>() else { return };
+    if presser != user_id {
+        toast(bot, cq_id, "🚫 This captcha is for someone else.").await;
+        return;
+    }
+
+    let key = chat_key(chat_id, user_id);
+    let Some(mut pending) = db.load::<Challenge>(CAPTCHAS, &key) else {
+        toast(bot, cq_id, "⌛ This captcha has expired.").await;
+        return;
+    };
+    let timeout_job = Job::CaptchaTimeout { user_id, name: pending.name.clone() };
+
+    if choice == pending.answer {
+        clear(bot, chat_id, user_id, db).await;
+        sched.cancel(chat_id, &timeout_job);
+        let mut p = RestrictChatMemberParams::new().use_independent_chat_permissions(true);
+        let perms = match pending.prior {
+            Some(r) => {
+                if r.until != 0 { p = p.until_date(r.until); }
+                r.perms
+            }
+            None => default_perms(bot, chat_id, db).await,
+        };
+        if let Err(e) = bot.restrict_chat_member(ChatId::from(chat_id), user_id, perms, Some(p)).await {
+            log::warn!("captcha: could not unmute {} in {}: {}", user_id, chat_id, e);
+        }
+        toast(bot, cq_id, "✅ Verified — welcome!").await;
+        return;
+    }
+
+    pending.attempts += 1;
+    if pending.attempts >= MAX_ATTEMPTS {
+        clear(bot, chat_id, user_id, db).await;
+        sched.cancel(chat_id, &timeout_job);
+        toast(bot, cq_id, "❌ Too many wrong answers.").await;
+        kick(bot, chat_id, user_id, pending.name, db, "failed captcha").await;
+        return;
+    }
+    let left = MAX_ATTEMPTS - pending.attempts;
+    db.save(CAPTCHAS, &key, &pending);
+    toast(bot, cq_id, &format!("❌ Wrong answer — {} {} left.", left, if left == 1 { "try" } else { "tries" })).await;
+}
+
+/// Scheduler deadline: kick if the challenge is still open.
+pub async fn expire(bot: &Bot, chat_id: i64, user_id: i64, db: &Db) {
+    if let Some(c) = clear(bot, chat_id, user_id, db).await {
+        kick(bot, chat_id, user_id, c.name, db, "captcha timed out").await;
+    }
+}
+
+/// Member left or was removed before answering.
+pub async fn on_leave(bot: &Bot, chat_id: i64, user_id: i64, db: &Db, sched: &Scheduler) {
+    if let Some(c) = clear(bot, chat_id, user_id, db).await {
+        sched.cancel(chat_id, &Job::CaptchaTimeout { user_id, name: c.name });
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /captcha [on|off|button|math|emoji]   /captchatime [duration]
+// ════════════════════════════════════════════════════════════════
+
+fn mode_label(mode: CaptchaMode) -> &'static str {
+    match mode {
+        CaptchaMode::Button => "button",
+        CaptchaMode::Math   => "math",
+        CaptchaMode::Emoji  => "emoji",
+    }
+}
+
+pub async fn handle_captcha(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let Some(a) = arg else {
+        let p = settings::load(db, chat_id).captcha;
+        reply(bot, chat_id, &format!(
+            "🧩 <b>Captcha</b> is <b>{}</b> · mode <b>{}</b> · timeout <b>{}</b>\n\n\
+            <i>Options:</i> <code>on</code> · <code>off</code> · <code>button</code> · <code>math</code> · <code>emoji</code>\n\
+            <i>Timeout:</i> <code>/captchatime 5m</code>",
+            if p.enabled { "on" } else { "off" }, mode_label(p.mode), duration::fmt(p.timeout))).await;
+        return;
+    };
+    let mode = match a.to_lowercase().as_str() {
+        "on"     => None,
+        "off"    => {
+            settings::update(db, chat_id, |s| s.captcha.enabled = false);
+            reply(bot, chat_id, "✅ Captcha disabled. New members can chat right away.").await;
+            return;
+        }
+        "button" => Some(CaptchaMode::Button),
+        "math"   => Some(CaptchaMode::Math),
+        "emoji"  => Some(CaptchaMode::Emoji),
+        _ => {
+            reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/captcha on|off|button|math|emoji</code>").await;
+            return;
+        }
+    };
+    let s = settings::update(db, chat_id, |s| {
+        s.captcha.enabled = true;
+        if let Some(m) = mode { s.captcha.mode = m; }
+    });
+    reply(bot, chat_id, &format!(
+        "✅ Captcha enabled (<b>{}</b>). New members are muted until they solve it within <b>{}</b>.\n\n\
+        <i>I need the ban-users right to mute and kick.</i>",
+        mode_label(s.captcha.mode), duration::fmt(s.captcha.timeout))).await;
+}
+
+pub async fn handle_captcha_time(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let Some(a) = arg else {
+        let p = settings::load(db, chat_id).captcha;
+        reply(bot, chat_id, &format!(
+            "⏳ New members have <b>{}</b> to solve the captcha.\n\n<i>Change with</i> <code>/captchatime 5m</code>",
+            duration::fmt(p.timeout))).await;
+        return;
+    };
+    match duration::parse(a) {
+        Ok(secs) if (MIN_RESTRICTION..=MAX_TIMEOUT).contains(&secs) => {
+            settings::update(db, chat_id, |s| s.captcha.timeout = secs);
+            reply(bot, chat_id, &format!("✅ Captcha timeout set to <b>{}</b>.", duration::fmt(secs))).await;
+        }
+        Ok(_) => reply(bot, chat_id, "⚠️ The timeout must be between 30s and 1d.").await,
+        Err(e) => reply(bot, chat_id, &format!(
+            "⚠️ <code>{}</code>: {}\n\nExamples: {}", html::escape(a), e, duration::EXAMPLES)).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn muted_member_who_rejoins_keeps_the_mute() {
+        let member = json!({
+            "status": "restricted", "user": { "id": 42, "is_bot": false, "first_name": "Eve" },
+            "is_member": true, "until_date": NOW + 3600,
+            "can_send_messages": false, "can_send_photos": false, "can_invite_users": true,
+        });
+        let r = prior_restriction(&member, NOW).expect("restriction kept");
+        assert_eq!(r.until, NOW + 3600);
+        assert_eq!(r.perms.can_send_messages, Some(false));
+        assert_eq!(r.perms.can_invite_users, Some(true));
+    }
+
+    #[test]
+    fn permanent_restriction_is_kept() {
+        let member = json!({ "status": "restricted", "until_date": 0, "can_send_messages": false });
+        assert_eq!(prior_restriction(&member, NOW).map(|r| r.until), Some(0));
+    }
+
+    #[test]
+    fn free_members_get_the_chat_defaults() {
+        assert_eq!(prior_restriction(&json!({ "status": "member" }), NOW), None);
+        assert_eq!(prior_restriction(&json!({ "status": "left" }), NOW), None);
+        let lapsed = json!({ "status": "restricted", "until_date": NOW - 1, "can_send_messages": false });
+        assert_eq!(prior_restriction(&lapsed, NOW), None);
+    }
+
+    #[test]
+    fn challenges_stored_before_the_fix_still_load() {
+        let old = json!({ "name": "Eve", "message_id": 7, "answer": "ok", "attempts": 1 });
+        let c: Challenge = serde_json::from_value(old).unwrap();
+        assert!(c.prior.is_none());
+    }
+}
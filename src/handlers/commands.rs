@@ -5,17 +5,22 @@
 use rand::Rng;
 use tgbotrs::{
     gen_methods::{
-        EditMessageTextParams, GetUserProfilePhotosParams, SendChatActionParams,
+        DeleteMyCommandsParams, EditMessageTextParams, GetUserProfilePhotosParams, SendChatActionParams,
         SendContactParams, SendDiceParams, SendLocationParams,
-        SendMessageParams, SendPollParams, SendVenueParams,
+        SendMessageParams, SendPollParams, SendVenueParams, SetMyCommandsParams,
     },
     types::{
-        BotCommand, InlineKeyboardButton, InlineKeyboardMarkup,
-        InputPollOption,
+        BotCommandScope, BotCommandScopeAllChatAdministrators, BotCommandScopeAllGroupChats,
+        BotCommandScopeAllPrivateChats, BotCommandScopeChat, BotCommandScopeChatAdministrators,
+        BotCommandScopeDefault, InlineKeyboardButton, InlineKeyboardMarkup, InputPollOption,
     },
     Bot, ChatId, ReplyMarkup,
 };
 
+use crate::admin_cache::AdminCache;
+use crate::registry::{self, Category::*, Menu};
+use crate::storage::{Db, CHATS};
+
 // ── Edit-or-send helper ───────────────────────────────────────────────────────
 
 pub async fn edit_or_send(
@@ -165,47 +170,12 @@ pub async fn handle_start(bot: &Bot, chat_id: i64, first_name: &str) {
 // ── /help ─────────────────────────────────────────────────────────────────────
 
 pub async fn handle_help(bot: &Bot, chat_id: i64) {
-    let text = "🦀 <b>Rustace Bot — Command Reference</b>\n\n\
-        <b>General</b>\n\
-        /start — Welcome &amp; main menu\n\
-        /help — This help message\n\
-        /about — About Rustace &amp; tgbotrs\n\
-        /menu — Show main menu\n\n\
-        <b>Fun</b>\n\
-        /dice — Roll a dice 🎲\n\
-        /darts — Throw darts 🎯\n\
-        /bowling — Bowling 🎳\n\
-        /basketball — Basketball 🏀\n\
-        /football — Football ⚽\n\
-        /slots — Slot machine 🎰\n\
-        /fact — Random Rust fact 💡\n\
-        /joke — Programmer joke 😂\n\
-        /magic8 — Magic 8-ball 🔮\n\
-        /coinflip — Coin flip 🪙\n\n\
-        <b>API Showcase</b>\n\
-        /botinfo — Bot information (get_me)\n\
-        /webhookinfo — Webhook status\n\
-        /membercount — Chat member count\n\
-        /admins — List administrators\n\
-        /invitelink — Generate invite link\n\
-        /mycommands — Show registered commands\n\
-        /myprofile — Your profile photos\n\
-        /library — Full library overview\n\n\
-        <b>Media</b>\n\
-        /photo — Demo photo\n\
-        /animation — Demo animation\n\
-        /location — Share location\n\
-        /venue — Share venue\n\
-        /contact — Share contact\n\
-        /poll — Create poll\n\
-        /textstyles — HTML formatting demo\n\n\
-        <b>Admin</b>\n\
-        /setcommands — Register bot commands\n\
-        /deletecommands — Delete commands\n\
-        /deletewebhook — Remove webhook\n\
-        /stats — Bot statistics\n\
-        /ping — Check bot latency 🏓\n\n\
-        <i>Source: github.com/ankit-chaubey/RustaceBot</i>";
+    let text = format!(
+        "🦀 <b>Rustace Bot — Command Reference</b>\n\n{}\n\n\
+        <i>Moderation &amp; admin commands: /modhelp</i>\n\
+        <i>Source: github.com/ankit-chaubey/RustaceBot</i>",
+        registry::help_sections(&[General, Fun, Showcase, Media, Filters, Notes, Broadcast, System]),
+    );
 
     let params = SendMessageParams::new()
         .parse_mode("HTML")
@@ -646,11 +616,11 @@ pub async fn handle_member_count(bot: &Bot, chat_id: i64, message_id: Option<i64
 
 // ── Chat admins ───────────────────────────────────────────────────────────────
 
-pub async fn handle_admins(bot: &Bot, chat_id: i64, message_id: Option<i64>) {
-    match bot.get_chat_administrators(chat_id).await {
+pub async fn handle_admins(bot: &Bot, chat_id: i64, message_id: Option<i64>, cache: &AdminCache) {
+    match cache.admins(bot, chat_id).await {
         Ok(admins) => {
             let admin_list: Vec<String> = admins.iter().map(|a| {
-                let v = serde_json::to_value(a).unwrap_or_default();
+                let v = a.raw();
                 let name = v.get("user").and_then(|u| u.get("first_name")).and_then(|n| n.as_str()).unwrap_or("Unknown");
                 let uname = v.get("user").and_then(|u| u.get("username")).and_then(|n| n.as_str())
                     .map(|u| format!(" (@{})", u)).unwrap_or_default();
@@ -890,84 +860,84 @@ pub async fn handle_webapp_info(bot: &Bot, chat_id: i64, message_id: Option<i64>
 }
 
 // ── Register commands ─────────────────────────────────────────────────────────
-
-pub async fn register_commands(bot: &Bot) -> Result<(), tgbotrs::BotError> {
-    let commands = vec![
-        BotCommand { command: "start".into(), description: "🦀 Welcome & main menu".into() },
-        BotCommand { command: "help".into(), description: "📖 Show all commands".into() },
-        BotCommand { command: "about".into(), description: "ℹ️ About Rustace & tgbotrs".into() },
-        BotCommand { command: "menu".into(), description: "📋 Show main menu".into() },
-        BotCommand { command: "dice".into(), description: "🎲 Roll a dice".into() },
-        BotCommand { command: "darts".into(), description: "🎯 Throw darts".into() },
-        BotCommand { command: "bowling".into(), description: "🎳 Play bowling".into() },
-        BotCommand { command: "basketball".into(), description: "🏀 Shoot hoops".into() },
-        BotCommand { command: "football".into(), description: "⚽ Kick the ball".into() },
-        BotCommand { command: "slots".into(), description: "🎰 Slot machine".into() },
-        BotCommand { command: "fact".into(), description: "💡 Random Rust fact".into() },
-        BotCommand { command: "joke".into(), description: "😂 Programmer joke".into() },
-        BotCommand { command: "magic8".into(), description: "🔮 Magic 8-ball".into() },
-        BotCommand { command: "coinflip".into(), description: "🪙 Flip a coin".into() },
-        BotCommand { command: "photo".into(), description: "🖼 Demo send_photo".into() },
-        BotCommand { command: "animation".into(), description: "🎬 Demo send_animation".into() },
-        BotCommand { command: "location".into(), description: "📍 Demo send_location".into() },
-        BotCommand { command: "venue".into(), description: "🏢 Demo send_venue".into() },
-        BotCommand { command: "contact".into(), description: "📞 Demo send_contact".into() },
-        BotCommand { command: "poll".into(), description: "📊 Create a poll".into() },
-        BotCommand { command: "textstyles".into(), description: "✨ HTML formatting demo".into() },
-        BotCommand { command: "botinfo".into(), description: "🤖 Bot info (get_me)".into() },
-        BotCommand { command: "webhookinfo".into(), description: "📡 Webhook status".into() },
-        BotCommand { command: "membercount".into(), description: "👥 Member count".into() },
-        BotCommand { command: "admins".into(), description: "👑 List admins".into() },
-        BotCommand { command: "invitelink".into(), description: "🔗 Get invite link".into() },
-        BotCommand { command: "mycommands".into(), description: "📋 Show commands".into() },
-        BotCommand { command: "myprofile".into(), description: "👤 Profile photos".into() },
-        BotCommand { command: "library".into(), description: "📚 Library overview".into() },
-        BotCommand { command: "stats".into(), description: "📊 Bot statistics".into() },
-        BotCommand { command: "ping".into(),  description: "🏓 Check bot latency & response time".into() },
-        BotCommand { command: "setcommands".into(), description: "⚙️ Register commands".into() },
-        BotCommand { command: "deletecommands".into(), description: "🗑 Delete commands".into() },
-        // ── Moderation ────────────────────────────────────────────────────────
-        BotCommand { command: "modhelp".into(), description: "🛡️ Moderation help".into() },
-        BotCommand { command: "ban".into(), description: "🔨 Ban user (reply) [duration]".into() },
-        BotCommand { command: "unban".into(), description: "✅ Unban user (reply)".into() },
-        BotCommand { command: "kick".into(), description: "👢 Kick user (reply)".into() },
-        BotCommand { command: "mute".into(), description: "🔇 Mute user (reply) [duration]".into() },
-        BotCommand { command: "unmute".into(), description: "🔊 Unmute user (reply)".into() },
-        BotCommand { command: "warn".into(), description: "⚠️ Warn user — 3 warns = auto-ban".into() },
-        BotCommand { command: "unwarn".into(), description: "✅ Remove a warning (reply)".into() },
-        BotCommand { command: "warns".into(), description: "📋 Check warnings (reply)".into() },
-        BotCommand { command: "delete".into(), description: "🗑 Delete replied message".into() },
-        BotCommand { command: "pin".into(), description: "📌 Pin replied message".into() },
-        BotCommand { command: "unpin".into(), description: "📌 Unpin current message".into() },
-        BotCommand { command: "ro".into(), description: "🔇 Read-only mode ON".into() },
-        BotCommand { command: "unro".into(), description: "🔊 Read-only mode OFF".into() },
-        // ── Admin ─────────────────────────────────────────────────────────────
-        BotCommand { command: "promote".into(), description: "⭐ Promote user [reply/id] [Title]".into() },
-        BotCommand { command: "demote".into(), description: "🔽 Demote user [reply/id]".into() },
-        BotCommand { command: "title".into(), description: "🏷️ Set admin title [reply/id] Title".into() },
-        BotCommand { command: "userinfo".into(), description: "👤 User info [reply/id/@user]".into() },
-        BotCommand { command: "whois".into(), description: "🔍 Same as /userinfo".into() },
-        // ── Filters ───────────────────────────────────────────────────────────
-        BotCommand { command: "filter".into(), description: "🔑 Add keyword auto-reply".into() },
-        BotCommand { command: "delfilter".into(), description: "🗑 Delete a filter".into() },
-        BotCommand { command: "filters".into(), description: "📋 List all active filters".into() },
-        // ── Notes ─────────────────────────────────────────────────────────────
-        BotCommand { command: "note".into(), description: "📝 Save a note".into() },
-        BotCommand { command: "get".into(), description: "📌 Get a saved note".into() },
-        BotCommand { command: "notes".into(), description: "📋 List all saved notes".into() },
-        BotCommand { command: "delnote".into(), description: "🗑 Delete a note".into() },
-        // ── Send / Post / Media ───────────────────────────────────────────────
-        BotCommand { command: "send".into(), description: "📨 Send message with inline buttons".into() },
-        BotCommand { command: "post".into(), description: "📢 Styled broadcast with buttons".into() },
-        BotCommand { command: "img".into(), description: "🖼 Send photo from URL".into() },
-        BotCommand { command: "vid".into(), description: "🎬 Send video from URL".into() },
-        BotCommand { command: "aud".into(), description: "🎵 Send audio from URL".into() },
-        BotCommand { command: "doc".into(), description: "📁 Send document from URL".into() },
-        BotCommand { command: "buttons".into(), description: "🎨 Colourful button showcase".into() },
-        BotCommand { command: "sendhelp".into(), description: "📡 /send and /post guide".into() },
+//   One list per BotCommandScope (see registry::Menu), repeated for every
+//   language in registry::TRANSLATIONS. Chats the bot has joined get their
+//   own chat / chat-admin scopes so the menu there is refreshed on join.
+
+fn menu_scopes(owner: Option<i64>) -> Vec<(BotCommandScope, Menu)> {
+    let mut scopes = vec![
+        (BotCommandScope::BotCommandScopeDefault(BotCommandScopeDefault { r#type: "default".into() }), Menu::Default),
+        (BotCommandScope::BotCommandScopeAllPrivateChats(BotCommandScopeAllPrivateChats { r#type: "all_private_chats".into() }), Menu::Private),
+        (BotCommandScope::BotCommandScopeAllGroupChats(BotCommandScopeAllGroupChats { r#type: "all_group_chats".into() }), Menu::Groups),
+        (BotCommandScope::BotCommandScopeAllChatAdministrators(BotCommandScopeAllChatAdministrators { r#type: "all_chat_administrators".into() }), Menu::Admins),
     ];
+    if let Some(id) = owner {
+        scopes.push((BotCommandScope::BotCommandScopeChat(BotCommandScopeChat { r#type: "chat".into(), chat_id: ChatId::Id(id) }), Menu::Owner));
+    }
+    scopes
+}
+
+fn chat_scopes(chat_id: i64) -> Vec<(BotCommandScope, Menu)> {
+    vec![
+        (BotCommandScope::BotCommandScopeChat(BotCommandScopeChat { r#type: "chat".into(), chat_id: ChatId::Id(chat_id) }), Menu::Groups),
+        (BotCommandScope::BotCommandScopeChatAdministrators(BotCommandScopeChatAdministrators { r#type: "chat_administrators".into(), chat_id: ChatId::Id(chat_id) }), Menu::Admins),
+    ]
+}
+
+async fn set_menu(bot: &Bot, scope: &BotCommandScope, menu: Menu) -> Result<(), tgbotrs::BotError> {
+    let p = SetMyCommandsParams::new().scope(scope.clone());
+    bot.set_my_commands(registry::bot_commands(menu, None), Some(p)).await?;
+    for lang in registry::languages() {
+        let p = SetMyCommandsParams::new().scope(scope.clone()).language_code(lang);
+        bot.set_my_commands(registry::bot_commands(menu, Some(lang)), Some(p)).await?;
+    }
+    Ok(())
+}
+
+async fn delete_menu(bot: &Bot, scope: &BotCommandScope) -> Result<(), tgbotrs::BotError> {
+    bot.delete_my_commands(Some(DeleteMyCommandsParams::new().scope(scope.clone()))).await?;
+    for lang in registry::languages() {
+        let p = DeleteMyCommandsParams::new().scope(scope.clone()).language_code(lang);
+        bot.delete_my_commands(Some(p)).await?;
+    }
+    Ok(())
+}
+
+fn known_chats(db: &Db) -> Vec<i64> {
+    db.scan(CHATS, "").into_iter().filter_map(|(k, _)| k.parse().ok()).collect()
+}
+
+/// Menus for a single group the bot is in.
+pub async fn register_chat_commands(bot: &Bot, chat_id: i64) -> Result<(), tgbotrs::BotError> {
+    for (scope, menu) in chat_scopes(chat_id) {
+        set_menu(bot, &scope, menu).await?;
+    }
+    Ok(())
+}
 
-    bot.set_my_commands(commands, None).await?;
+/// Global menus. A scope that fails is logged and skipped. Per-chat menus
+/// are only set when the bot joins a chat, so startup stays a few calls
+/// however many chats the bot is in.
+pub async fn register_commands(bot: &Bot, owner: Option<i64>) -> Result<(), tgbotrs::BotError> {
+    for (scope, menu) in menu_scopes(owner) {
+        if let Err(e) = set_menu(bot, &scope, menu).await {
+            log::warn!("could not register the {:?} menu: {}", menu, e);
+        }
+    }
     log::info!("✅ Bot commands registered successfully");
     Ok(())
 }
+
+pub async fn delete_commands(bot: &Bot, owner: Option<i64>, db: &Db) -> Result<(), tgbotrs::BotError> {
+    for (scope, _) in menu_scopes(owner) {
+        delete_menu(bot, &scope).await?;
+    }
+    for chat_id in known_chats(db) {
+        for (scope, _) in chat_scopes(chat_id) {
+            if let Err(e) = delete_menu(bot, &scope).await {
+                log::warn!("could not delete commands in {}: {}", chat_id, e);
+            }
+        }
+    }
+    Ok(())
+}
@@ -6,16 +6,9 @@
 //  Auto-triggers when any message contains a keyword.
 // ════════════════════════════════════════════════════════════════
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 use tgbotrs::{gen_methods::SendMessageParams, types::InlineKeyboardMarkup, Bot, ReplyMarkup};
 use super::commands::btn;
-
-pub type FilterStore = Arc<Mutex<HashMap<(i64, String), String>>>;
-
-pub fn new_filter_store() -> FilterStore {
-    Arc::new(Mutex::new(HashMap::new()))
-}
+use crate::storage::{chat_key, chat_prefix, Db, FILTERS};
 
 fn he(s: &str) -> String { s.replace('&',"&amp;").replace('<',"&lt;").replace('>',"&gt;") }
 
@@ -26,7 +19,7 @@ async fn reply(bot: &Bot, chat_id: i64, text: &str) {
 }
 
 // /filter keyword response text
-pub async fn handle_set_filter(bot: &Bot, chat_id: i64, args: &[&str], store: &FilterStore) {
+pub async fn handle_set_filter(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
     if args.len() < 2 {
         reply(bot, chat_id,
             "⚠️ <b>Usage:</b> <code>/filter keyword response text</code>\n\n\
@@ -39,7 +32,7 @@ pub async fn handle_set_filter(bot: &Bot, chat_id: i64, args: &[&str], store: &F
     }
     let keyword  = args[0].to_lowercase();
     let response = args[1..].join(" ");
-    { store.lock().unwrap().insert((chat_id, keyword.clone()), response.clone()); }
+    db.save(FILTERS, &chat_key(chat_id, &keyword), &response);
     reply(bot, chat_id, &format!(
         "✅ <b>Filter saved!</b>\n\n🔑 Keyword: <code>{}</code>\n💬 Response: {}",
         he(&keyword), he(&response)
@@ -47,13 +40,13 @@ pub async fn handle_set_filter(bot: &Bot, chat_id: i64, args: &[&str], store: &F
 }
 
 // /delfilter keyword
-pub async fn handle_del_filter(bot: &Bot, chat_id: i64, arg: Option<&str>, store: &FilterStore) {
+pub async fn handle_del_filter(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
     let Some(kw) = arg else {
         reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/delfilter keyword</code>").await;
         return;
     };
     let keyword = kw.to_lowercase();
-    let removed = { store.lock().unwrap().remove(&(chat_id, keyword.clone())).is_some() };
+    let removed = db.delete(FILTERS, &chat_key(chat_id, &keyword));
     if removed {
         reply(bot, chat_id, &format!("🗑️ <b>Filter deleted:</b> <code>{}</code>", he(&keyword))).await;
     } else {
@@ -62,12 +55,12 @@ pub async fn handle_del_filter(bot: &Bot, chat_id: i64, arg: Option<&str>, store
 }
 
 // /filters  — list all
-pub async fn handle_list_filters(bot: &Bot, chat_id: i64, store: &FilterStore) {
-    let mut pairs: Vec<(String, String)> = {
-        let s = store.lock().unwrap();
-        s.iter().filter(|((cid,_),_)| *cid == chat_id)
-            .map(|((_,k),v)| (k.clone(), v.clone())).collect()
-    };
+pub async fn handle_list_filters(bot: &Bot, chat_id: i64, db: &Db) {
+    let prefix = chat_prefix(chat_id);
+    let mut pairs: Vec<(String, String)> = db.load_all::<String>(FILTERS, &prefix)
+        .into_iter()
+        .map(|(k, v)| (k[prefix.len()..].to_string(), v))
+        .collect();
     if pairs.is_empty() {
         reply(bot, chat_id, "📂 <b>No filters set.</b>\nUse <code>/filter keyword response</code> to add one.").await;
         return;
@@ -81,15 +74,13 @@ pub async fn handle_list_filters(bot: &Bot, chat_id: i64, store: &FilterStore) {
 }
 
 // Auto-trigger: called on every message. Returns true if matched.
-pub async fn check_filters(bot: &Bot, chat_id: i64, text: &str, store: &FilterStore) -> bool {
+pub async fn check_filters(bot: &Bot, chat_id: i64, text: &str, db: &Db) -> bool {
     let lower = text.to_lowercase();
-    let matched: Option<String> = {
-        let s = store.lock().unwrap();
-        s.iter()
-            .filter(|((cid, kw), _)| *cid == chat_id && lower.contains(kw.as_str()))
-            .map(|(_, v)| v.clone())
-            .next()
-    };
+    let prefix = chat_prefix(chat_id);
+    let matched: Option<String> = db.load_all::<String>(FILTERS, &prefix)
+        .into_iter()
+        .find(|(k, _)| lower.contains(&k[prefix.len()..]))
+        .map(|(_, v)| v);
     if let Some(resp) = matched {
         let p = SendMessageParams::new().parse_mode("HTML");
         let _ = bot.send_message(chat_id, resp, Some(p)).await;
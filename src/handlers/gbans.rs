@@ -0,0 +1,221 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Global Bans
+//  One ban list shared by every chat the bot moderates, kept by
+//  sudo users. Chats opt in with /gbanstat on; there, a listed user
+//  is banned as soon as they join or speak. Members are also checked
+//  against the list when the bot is added or a chat opts in.
+//  /gban <user> <reason>   /ungban <user>   /gbanstat [on|off]
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::{BanChatMemberParams, UnbanChatMemberParams},
+    Bot, ChatId,
+};
+
+use super::audit::{self, Action, Entry};
+use crate::duration;
+use crate::html::{self, reply};
+use crate::permissions;
+use crate::roles::{Role, Roles};
+use crate::settings;
+use crate::storage::{Db, CHATS, GBANS};
+use crate::targets::{self, Resolved, Target};
+
+/// A join is reported twice (service message and `chat_member`); a
+/// removal within this many seconds is the same one.
+const REMOVAL_DEDUP: i64 = 60;
+
+/// A listed user, keyed by user id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GlobalBan {
+    name: String,
+    reason: String,
+    by: i64,
+    at: i64,
+    /// (chat, time) of every removal, so /ungban can undo exactly those.
+    #[serde(default)]
+    removed: Vec<(i64, i64)>,
+}
+
+/// Chats the bot is in that enforce the list.
+fn enforcing_chats(db: &Db) -> Vec<i64> {
+    db.scan(CHATS, "").into_iter()
+        .filter_map(|(k, _)| k.parse::<i64>().ok())
+        .filter(|id| settings::load(db, *id).gbans)
+        .collect()
+}
+
+/// Whether `user_id` is currently a member of `chat_id` (admins aside).
+async fn is_member(bot: &Bot, chat_id: i64, user_id: i64) -> bool {
+    match permissions::fetch_member(bot, chat_id, user_id).await {
+        Ok(m) => m.status == "member" || (m.status == "restricted" && m.raw()["is_member"].as_bool().unwrap_or(false)),
+        Err(_) => false,
+    }
+}
+
+/// Bans a listed user from one chat, records it, and tells the chat.
+async fn remove(bot: &Bot, chat_id: i64, user_id: i64, db: &Db) -> bool {
+    let now = duration::now();
+    let key = user_id.to_string();
+    let mut fresh = false;
+    let mut previous = None;
+    // Claim the removal first so the second report of a join doesn't ban twice.
+    let entry = db.modify::<GlobalBan, _>(GBANS, &key, |old| {
+        old.map(|mut g| {
+            if !g.removed.iter().any(|(c, at)| *c == chat_id && now - at < REMOVAL_DEDUP) {
+                fresh = true;
+                previous = g.removed.iter().copied().find(|(c, _)| *c == chat_id);
+                g.removed.retain(|(c, _)| *c != chat_id);
+                g.removed.push((chat_id, now));
+            }
+            g
+        })
+    });
+    let Some(g) = entry else { return false };
+    if !fresh { return true; }
+
+    let params = BanChatMemberParams::new().revoke_messages(true);
+    if let Err(e) = bot.ban_chat_member(ChatId::from(chat_id), user_id, Some(params)).await {
+        log::warn!("gban: could not remove {} from {}: {}", user_id, chat_id, e);
+        // Undo the claim, so a retry bans and /ungban skips this chat.
+        db.modify::<GlobalBan, _>(GBANS, &key, |old| {
+            old.map(|mut g| {
+                g.removed.retain(|r| *r != (chat_id, now));
+                g.removed.extend(previous);
+                g
+            })
+        });
+        return false;
+    }
+    let t = Target { id: user_id, name: g.name.clone() };
+    audit::record(bot, db, Entry::new(chat_id, bot.me.id, Action::Ban).target(&t)
+        .reason(&format!("global ban: {}", g.reason))).await;
+    reply(bot, chat_id, &format!(
+        "🌐 <a href=\"tg://user?id={}\">{}</a> is on the global ban list and was removed.\n<b>Reason:</b> {}",
+        user_id, html::escape(&g.name), html::escape(&g.reason))).await;
+    true
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Enforcement — joins and messages in chats that opted in
+//  Returns true if the user was removed.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn check(bot: &Bot, chat_id: i64, user_id: i64, db: &Db) -> bool {
+    if db.get(GBANS, &user_id.to_string()).is_none() { return false; }
+    if !settings::load(db, chat_id).gbans { return false; }
+    remove(bot, chat_id, user_id, db).await
+}
+
+/// Checks every listed user against the members of `chat_id`. Where the
+/// chat enforces the list they are removed; otherwise the admins are told.
+pub async fn sweep(bot: &Bot, chat_id: i64, db: &Db) {
+    let enforce = settings::load(db, chat_id).gbans;
+    let mut found = 0;
+    for (key, _) in db.scan(GBANS, "") {
+        let Ok(user_id) = key.parse::<i64>() else { continue };
+        if !is_member(bot, chat_id, user_id).await { continue; }
+        found += 1;
+        if enforce { remove(bot, chat_id, user_id, db).await; }
+    }
+    if found > 0 && !enforce {
+        reply(bot, chat_id, &format!(
+            "🌐 <b>{}</b> member(s) of this chat are on the global ban list.\n\n\
+            <i>An admin can remove them with</i> <code>/gbanstat on</code>.", found)).await;
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /gban <user> <reason>   /ungban <user>   — sudo only
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_gban(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, roles: &Roles) {
+    let Some(t) = targets::require(bot, chat_id, target, "/gban", "<reason>").await else { return };
+    let reason = target.rest.trim();
+    if reason.is_empty() {
+        reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/gban &lt;user&gt; &lt;reason&gt;</code>\n\n<i>A reason is required.</i>").await;
+        return;
+    }
+    if t.id == bot.me.id || roles.role_of(t.id) >= Role::Sudo {
+        reply(bot, chat_id, "🚫 <b>Refused.</b> That user runs this bot.").await;
+        return;
+    }
+
+    let key = t.id.to_string();
+    let mut updated = false;
+    db.modify::<GlobalBan, _>(GBANS, &key, |old| {
+        updated = old.is_some();
+        let removed = old.map(|g| g.removed).unwrap_or_default();
+        Some(GlobalBan { name: t.name.clone(), reason: reason.to_string(), by, at: duration::now(), removed })
+    });
+    let who = format!("<a href=\"tg://user?id={}\">{}</a>", t.id, html::escape(&t.name));
+    if updated {
+        reply(bot, chat_id, &format!("🌐 Updated the global ban reason for {}.\n<b>Reason:</b> {}", who, html::escape(reason))).await;
+        return;
+    }
+
+    let mut count = 0;
+    for chat in enforcing_chats(db) {
+        if is_member(bot, chat, t.id).await && remove(bot, chat, t.id, db).await { count += 1; }
+    }
+    log::info!("gban: {} listed by {}", t.id, by);
+    reply(bot, chat_id, &format!(
+        "🌐 {} is now <b>globally banned</b>.\n<b>Reason:</b> {}\n\n\
+        <i>Removed from {} chat(s); every chat with</i> <code>/gbanstat on</code> <i>will remove them on sight.</i>",
+        who, html::escape(reason), count)).await;
+}
+
+pub async fn handle_ungban(bot: &Bot, chat_id: i64, target: &Resolved, db: &Db) {
+    let Some(t) = targets::require(bot, chat_id, target, "/ungban", "").await else { return };
+    let key = t.id.to_string();
+    let Some(g) = db.load::<GlobalBan>(GBANS, &key) else {
+        reply(bot, chat_id, &format!("❓ <code>{}</code> is not globally banned.", t.id)).await;
+        return;
+    };
+    db.delete(GBANS, &key);
+
+    // Only the bans this list caused are lifted; local bans stay.
+    let mut count = 0;
+    for (chat, _) in &g.removed {
+        let p = UnbanChatMemberParams::new().only_if_banned(true);
+        if bot.unban_chat_member(ChatId::from(*chat), t.id, Some(p)).await.is_ok() {
+            let target = Target { id: t.id, name: g.name.clone() };
+            audit::record(bot, db, Entry::new(*chat, bot.me.id, Action::Unban).target(&target).reason("global ban lifted")).await;
+            count += 1;
+        }
+    }
+    log::info!("gban: {} lifted", t.id);
+    reply(bot, chat_id, &format!(
+        "✅ <a href=\"tg://user?id={}\">{}</a> is no longer globally banned.\n\n<i>Unbanned in {} chat(s).</i>",
+        t.id, html::escape(&g.name), count)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /gbanstat [on|off] — whether this chat enforces the list
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_gbanstat(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let on = match arg.map(str::to_lowercase).as_deref() {
+        Some("on" | "yes") => true,
+        Some("off" | "no") => false,
+        Some(_) => {
+            reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/gbanstat on|off</code>").await;
+            return;
+        }
+        None => {
+            let state = if settings::load(db, chat_id).gbans { "on" } else { "off" };
+            reply(bot, chat_id, &format!(
+                "🌐 Global bans in this chat: <b>{}</b>\n\n<i>Change with</i> <code>/gbanstat on</code> <i>or</i> <code>/gbanstat off</code>",
+                state)).await;
+            return;
+        }
+    };
+    settings::update(db, chat_id, |s| s.gbans = on);
+    if on {
+        reply(bot, chat_id, "✅ Global bans are <b>on</b>. Listed users are removed when they join or speak.").await;
+        sweep(bot, chat_id, db).await;
+    } else {
+        reply(bot, chat_id, "✅ Global bans are <b>off</b> for this chat.").await;
+    }
+}
@@ -0,0 +1,254 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Welcome & Goodbye
+//  Templates are HTML with placeholders, plus the `[Label | value]`
+//  button lines understood by /send:
+//    {first} {last} {fullname} {username} {mention} {id}
+//    {chatname} {count} {rules}
+//  /setwelcome <text|off>   /setgoodbye <text|off>
+//  /cleanwelcome [on|off]   /cleanservice [on|off]
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::{
+    gen_methods::SendMessageParams,
+    types::{InlineKeyboardMarkup, User},
+    Bot, ChatId, ReplyMarkup,
+};
+
+use super::broadcast::split_body_buttons;
+use crate::html::{self, reply};
+use crate::settings::{self, ChatSettings};
+use crate::storage::{chat_key, Db, NOTES};
+
+const PLACEHOLDERS: &str = "<code>{first}</code> <code>{last}</code> <code>{fullname}</code> \
+    <code>{username}</code> <code>{mention}</code> <code>{id}</code> \
+    <code>{chatname}</code> <code>{count}</code> <code>{rules}</code>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Welcome,
+    Goodbye,
+}
+
+impl Kind {
+    fn command(self) -> &'static str {
+        match self {
+            Kind::Welcome => "/setwelcome",
+            Kind::Goodbye => "/setgoodbye",
+        }
+    }
+
+    fn template(self, s: &mut ChatSettings) -> &mut Option<String> {
+        match self {
+            Kind::Welcome => &mut s.greetings.welcome,
+            Kind::Goodbye => &mut s.greetings.goodbye,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Welcome => "Welcome",
+            Kind::Goodbye => "Goodbye",
+        }
+    }
+}
+
+// ── Rendering ────────────────────────────────────────────────────────────────
+
+/// Fills the placeholders in one pass, so a name like `{rules}` stays
+/// literal. User-supplied values are escaped; `{rules}` is the chat's
+/// `rules` note as saved (already HTML). Unknown `{words}` are kept.
+async fn render(bot: &Bot, template: &str, user: &User, chat_id: i64, chat_title: &str, db: &Db) -> String {
+    let count = match template.contains("{count}") {
+        true  => bot.get_chat_member_count(chat_id).await.map(|n| n.to_string()).unwrap_or_else(|_| "?".into()),
+        false => String::new(),
+    };
+    let rules: Option<String> = db.load(NOTES, &chat_key(chat_id, "rules"));
+    let fullname = match &user.last_name {
+        Some(last) => format!("{} {}", user.first_name, last),
+        None => user.first_name.clone(),
+    };
+
+    let value = |name: &str| -> Option<String> {
+        Some(match name {
+            "first"    => html::escape(&user.first_name),
+            "last"     => html::escape(user.last_name.as_deref().unwrap_or("")),
+            "fullname" => html::escape(&fullname),
+            "username" => match &user.username {
+                Some(u) => format!("@{}", html::escape(u)),
+                None    => html::escape(&user.first_name),
+            },
+            "mention"  => format!("<a href=\"tg://user?id={}\">{}</a>", user.id, html::escape(&user.first_name)),
+            "id"       => user.id.to_string(),
+            "chatname" => html::escape(chat_title),
+            "count"    => count.clone(),
+            "rules"    => rules.clone().unwrap_or_else(|| "<i>No rules set — save them with /note rules …</i>".into()),
+            _          => return None,
+        })
+    };
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find('}').and_then(|close| value(&after[..close]).map(|v| (close, v))) {
+            Some((close, v)) => { out.push_str(&v); rest = &after[close + 1..]; }
+            None             => { out.push('{'); rest = after; }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Renders and sends a template; returns the message id. Buttons are split
+/// off first so a name containing `[a | b]` can't turn into a button.
+async fn send(bot: &Bot, template: &str, user: &User, chat_id: i64, chat_title: &str, db: &Db) -> Result<i64, tgbotrs::BotError> {
+    let (body, rows) = split_body_buttons(template);
+    let body = render(bot, &body, user, chat_id, chat_title, db).await;
+    let mut p = SendMessageParams::new().parse_mode("HTML");
+    if !rows.is_empty() {
+        p = p.reply_markup(ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup { inline_keyboard: rows }));
+    }
+    bot.send_message(chat_id, body, Some(p)).await.map(|m| m.message_id)
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Service messages — called for `new_chat_members` / `left_chat_member`
+// ════════════════════════════════════════════════════════════════
+
+pub async fn on_join(bot: &Bot, chat_id: i64, chat_title: &str, users: &[User], service_msg_id: i64, db: &Db) {
+    let g = settings::load(db, chat_id).greetings;
+    if g.clean_service {
+        let _ = bot.delete_message(ChatId::from(chat_id), service_msg_id).await;
+    }
+    let Some(template) = g.welcome else { return };
+
+    // The bot's own arrival is greeted by the my_chat_member handler.
+    for user in users.iter().filter(|u| u.id != bot.me.id) {
+        match send(bot, &template, user, chat_id, chat_title, db).await {
+            Ok(id) => {
+                let old = settings::load(db, chat_id).greetings.last_welcome;
+                settings::update(db, chat_id, |s| s.greetings.last_welcome = Some(id));
+                if let (true, Some(old)) = (g.clean_welcome, old) {
+                    let _ = bot.delete_message(ChatId::from(chat_id), old).await;
+                }
+            }
+            Err(e) => log::warn!("greetings: welcome failed in {}: {}", chat_id, e),
+        }
+    }
+}
+
+pub async fn on_leave(bot: &Bot, chat_id: i64, chat_title: &str, user: &User, service_msg_id: i64, db: &Db) {
+    let g = settings::load(db, chat_id).greetings;
+    if g.clean_service {
+        let _ = bot.delete_message(ChatId::from(chat_id), service_msg_id).await;
+    }
+    let Some(template) = g.goodbye else { return };
+    if user.id == bot.me.id { return; }
+
+    if let Err(e) = send(bot, &template, user, chat_id, chat_title, db).await {
+        log::warn!("greetings: goodbye failed in {}: {}", chat_id, e);
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /setwelcome <text|off>   /setgoodbye <text|off>
+//  The template is previewed for the admin first; it is only saved
+//  if Telegram accepts the rendered HTML.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_set_welcome(bot: &Bot, chat_id: i64, chat_title: &str, from: Option<&User>, raw: &str, db: &Db) {
+    set_template(bot, chat_id, chat_title, from, raw, db, Kind::Welcome).await;
+}
+
+pub async fn handle_set_goodbye(bot: &Bot, chat_id: i64, chat_title: &str, from: Option<&User>, raw: &str, db: &Db) {
+    set_template(bot, chat_id, chat_title, from, raw, db, Kind::Goodbye).await;
+}
+
+async fn set_template(bot: &Bot, chat_id: i64, chat_title: &str, from: Option<&User>, raw: &str, db: &Db, kind: Kind) {
+    if raw.is_empty() {
+        let current = kind.template(&mut settings::load(db, chat_id)).clone();
+        let text = match current {
+            Some(t) => format!("👋 <b>{} message</b>\n\n<pre>{}</pre>\n\n<i>Disable with</i> <code>{} off</code>",
+                kind.label(), html::escape(&t), kind.command()),
+            None => format!("👋 No {} message set.", kind.label().to_lowercase()),
+        };
+        reply(bot, chat_id, &format!(
+            "{}\n\n<b>Usage:</b>\n<pre>{} Hi {{mention}}, welcome to {{chatname}}!\n\
+            [📜 Rules | https://t.me/yourchannel/1]</pre>\n\
+            <b>Placeholders:</b> {}", text, kind.command(), PLACEHOLDERS)).await;
+        return;
+    }
+
+    if raw.eq_ignore_ascii_case("off") {
+        settings::update(db, chat_id, |s| *kind.template(s) = None);
+        reply(bot, chat_id, &format!("✅ {} message disabled.", kind.label())).await;
+        return;
+    }
+
+    let Some(user) = from else { return };
+    let (body, _) = split_body_buttons(raw);
+    if body.is_empty() {
+        reply(bot, chat_id, "⚠️ Please add message text above the button lines.").await;
+        return;
+    }
+    if let Err(e) = send(bot, raw, user, chat_id, chat_title, db).await {
+        reply(bot, chat_id, &format!(
+            "❌ Telegram rejected this template: <code>{}</code>\n<i>Check the HTML tags and button lines.</i>",
+            html::escape(&e.to_string()))).await;
+        return;
+    }
+    let template = raw.to_string();
+    settings::update(db, chat_id, |s| *kind.template(s) = Some(template));
+    reply(bot, chat_id, &format!("✅ {} message saved — preview above.", kind.label())).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /cleanwelcome [on|off]   /cleanservice [on|off]
+// ════════════════════════════════════════════════════════════════
+
+fn parse_switch(arg: Option<&str>) -> Option<Option<bool>> {
+    match arg.map(str::to_lowercase).as_deref() {
+        None                      => Some(None),
+        Some("on" | "yes" | "1")  => Some(Some(true)),
+        Some("off" | "no" | "0")  => Some(Some(false)),
+        Some(_)                   => None,
+    }
+}
+
+fn on_off(b: bool) -> &'static str {
+    if b { "on" } else { "off" }
+}
+
+pub async fn handle_clean_welcome(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    match parse_switch(arg) {
+        Some(None) => {
+            let on = settings::load(db, chat_id).greetings.clean_welcome;
+            reply(bot, chat_id, &format!(
+                "🧹 Deleting the previous welcome: <b>{}</b>\n\n<i>Usage:</i> <code>/cleanwelcome on|off</code>",
+                on_off(on))).await;
+        }
+        Some(Some(on)) => {
+            settings::update(db, chat_id, |s| s.greetings.clean_welcome = on);
+            reply(bot, chat_id, &format!("✅ Deleting the previous welcome: <b>{}</b>.", on_off(on))).await;
+        }
+        None => reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/cleanwelcome on|off</code>").await,
+    }
+}
+
+pub async fn handle_clean_service(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    match parse_switch(arg) {
+        Some(None) => {
+            let on = settings::load(db, chat_id).greetings.clean_service;
+            reply(bot, chat_id, &format!(
+                "🧹 Deleting join/leave service messages: <b>{}</b>\n\n<i>Usage:</i> <code>/cleanservice on|off</code>",
+                on_off(on))).await;
+        }
+        Some(Some(on)) => {
+            settings::update(db, chat_id, |s| s.greetings.clean_service = on);
+            let hint = if on { "\n<i>I need the delete-messages right for this.</i>" } else { "" };
+            reply(bot, chat_id, &format!("✅ Deleting join/leave service messages: <b>{}</b>.{}", on_off(on), hint)).await;
+        }
+        None => reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/cleanservice on|off</code>").await,
+    }
+}
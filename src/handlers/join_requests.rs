@@ -0,0 +1,342 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Join Requests
+//  Per-chat policy for `chat_join_request`: approve, decline, or
+//  queue for review. Review cards show the applicant's profile, so
+//  they only go to the log channel, never to the group; Approve /
+//  Decline buttons decide. An optional questionnaire is sent to the
+//  applicant in private and their reply is attached.
+//  While anti-raid is on, requests are parked and replayed after.
+//  /joinpolicy [approve|decline|review]   /joinquestions [q1 | q2|off]
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::{AnswerCallbackQueryParams, EditMessageTextParams, SendMessageParams},
+    types::{ChatJoinRequest, InlineKeyboardButton, InlineKeyboardMarkup},
+    Bot, ChatId, ReplyMarkup,
+};
+
+use super::audit::{self, Action, Entry};
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::html::{self, reply};
+use crate::permissions::Right;
+use crate::settings::{self, JoinMode};
+use crate::storage::{chat_key, chat_prefix, Db, CHATS, JOIN_REQUESTS, PAUSED_JOINS, QUESTIONNAIRES};
+use crate::targets::{self, Target};
+
+const MAX_QUESTIONS: usize = 5;
+const MAX_ANSWER_LEN: usize = 1500;
+
+/// A queued request, keyed by `chat_key(chat, user)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Application {
+    name: String,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    bio: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    premium: bool,
+    /// Name of the invite link used, if any.
+    #[serde(default)]
+    invite: Option<String>,
+    at: i64,
+    #[serde(default)]
+    answers: Option<String>,
+    /// (chat, message) of the review card.
+    #[serde(default)]
+    card: Option<(i64, i64)>,
+}
+
+fn chat_title(db: &Db, chat_id: i64) -> String {
+    db.get(CHATS, &chat_id.to_string())
+        .and_then(|v| v.get("title").and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_else(|| chat_id.to_string())
+}
+
+// ── Review card ──────────────────────────────────────────────────────────────
+//   Button data is `join:<a|d>:<chat_id>:<user_id>`, so cards work from a
+//   log channel too.
+
+fn card_text(db: &Db, chat_id: i64, user_id: i64, app: &Application) -> String {
+    let mut text = format!(
+        "🚪 <b>Join request</b> · {}\n\n\
+        👤 <a href=\"tg://user?id={}\">{}</a> (<code>{}</code>)",
+        html::escape(&chat_title(db, chat_id)), user_id, html::escape(&app.name), user_id);
+    if let Some(u) = &app.username { text.push_str(&format!("\n🔗 @{}", html::escape(u))); }
+    if let Some(l) = &app.language { text.push_str(&format!("\n🌐 {}", html::escape(l))); }
+    if app.premium { text.push_str("\n⭐ Premium"); }
+    if let Some(b) = &app.bio { text.push_str(&format!("\n📝 <i>{}</i>", html::escape(b))); }
+    if let Some(i) = &app.invite { text.push_str(&format!("\n🎟 Invite link: {}", html::escape(i))); }
+    text.push_str(&format!("\n🕐 {} ago", duration::age(duration::now() - app.at)));
+    match &app.answers {
+        Some(a) => text.push_str(&format!("\n\n<b>Answers:</b>\n{}", html::escape(a))),
+        None if !settings::load(db, chat_id).join.questions.is_empty() =>
+            text.push_str("\n\n<i>Waiting for questionnaire answers…</i>"),
+        None => {}
+    }
+    text
+}
+
+fn card_keyboard(chat_id: i64, user_id: i64) -> InlineKeyboardMarkup {
+    let b = |text: &str, verb: &str| InlineKeyboardButton {
+        text: text.to_string(),
+        callback_data: Some(format!("join:{}:{}:{}", verb, chat_id, user_id)),
+        ..Default::default()
+    };
+    InlineKeyboardMarkup { inline_keyboard: vec![vec![b("✅ Approve", "a"), b("❌ Decline", "d")]] }
+}
+
+async fn edit_card(bot: &Bot, card: (i64, i64), text: String, kb: Option<InlineKeyboardMarkup>) {
+    let mut p = EditMessageTextParams::new()
+        .chat_id(ChatId::from(card.0))
+        .message_id(card.1)
+        .parse_mode("HTML");
+    if let Some(kb) = kb { p = p.reply_markup(kb); }
+    let _ = bot.edit_message_text(text, Some(p)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Incoming request
+// ════════════════════════════════════════════════════════════════
+
+pub async fn on_request(bot: &Bot, jr: &ChatJoinRequest, db: &Db) {
+    let chat_id = jr.chat.id;
+    let user = &jr.from;
+    let settings = settings::load(db, chat_id);
+
+    if settings.raid.active(duration::now()) {
+        db.save(PAUSED_JOINS, &chat_key(chat_id, user.id), jr);
+        return;
+    }
+
+    match settings.join.mode {
+        JoinMode::Approve => {
+            let _ = bot.approve_chat_join_request(chat_id, user.id).await;
+            return;
+        }
+        JoinMode::Decline => {
+            let _ = bot.decline_chat_join_request(chat_id, user.id).await;
+            let t = Target { id: user.id, name: user.first_name.clone() };
+            audit::record(bot, db, Entry::new(chat_id, bot.me.id, Action::DeclineJoin)
+                .target(&t).reason("join policy: decline")).await;
+            return;
+        }
+        JoinMode::Review => {}
+    }
+    // Without a log channel the request waits in Telegram's own list.
+    let Some(dest) = settings.log_channel else {
+        log::warn!("join: {} reviews requests but has no log channel; {} left pending", chat_id, user.id);
+        return;
+    };
+
+    targets::remember(db, user);
+    let mut app = Application {
+        name: user.first_name.clone(),
+        username: user.username.clone(),
+        bio: jr.bio.clone(),
+        language: user.language_code.clone(),
+        premium: user.is_premium.unwrap_or(false),
+        invite: jr.invite_link.as_ref().map(|l| l.name.clone().unwrap_or_else(|| l.invite_link.clone())),
+        at: duration::now(),
+        answers: None,
+        card: None,
+    };
+
+    let questions = &settings.join.questions;
+    if !questions.is_empty() {
+        let list: Vec<String> = questions.iter().enumerate()
+            .map(|(i, q)| format!("{}. {}", i + 1, html::escape(q))).collect();
+        let text = format!(
+            "👋 Thanks for your request to join <b>{}</b>!\n\n\
+            Please answer these questions in <b>one message</b>:\n\n{}",
+            html::escape(&chat_title(db, chat_id)), list.join("\n"));
+        let p = SendMessageParams::new().parse_mode("HTML");
+        match bot.send_message(jr.user_chat_id, text, Some(p)).await {
+            Ok(_)  => db.save(QUESTIONNAIRES, &user.id.to_string(), &chat_id),
+            Err(e) => log::warn!("join: could not send questionnaire to {}: {}", user.id, e),
+        }
+    }
+
+    let p = SendMessageParams::new()
+        .parse_mode("HTML")
+        .reply_markup(ReplyMarkup::InlineKeyboard(card_keyboard(chat_id, user.id)));
+    match bot.send_message(dest, card_text(db, chat_id, user.id, &app), Some(p)).await {
+        Ok(sent) => app.card = Some((dest, sent.message_id)),
+        Err(e)   => log::warn!("join: could not post review card for {} in {}: {}", chat_id, dest, e),
+    }
+    db.save(JOIN_REQUESTS, &chat_key(chat_id, user.id), &app);
+}
+
+/// Runs the requests parked during anti-raid through the normal policy.
+pub async fn resume(bot: &Bot, chat_id: i64, db: &Db) -> usize {
+    let parked = db.load_all::<ChatJoinRequest>(PAUSED_JOINS, &chat_prefix(chat_id));
+    for (key, jr) in &parked {
+        db.delete(PAUSED_JOINS, key);
+        on_request(bot, jr, db).await;
+    }
+    parked.len()
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Questionnaire answer — next private text from the applicant
+//  Returns true if the message was consumed.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn take_answers(bot: &Bot, user_id: i64, text: &str, db: &Db) -> bool {
+    let Some(chat_id) = db.load::<i64>(QUESTIONNAIRES, &user_id.to_string()) else { return false };
+    db.delete(QUESTIONNAIRES, &user_id.to_string());
+
+    let answers: String = text.chars().take(MAX_ANSWER_LEN).collect();
+    let app = db.modify::<Application, _>(JOIN_REQUESTS, &chat_key(chat_id, user_id), |old| {
+        old.map(|a| Application { answers: Some(answers), ..a })
+    });
+    let Some(app) = app else { return false };
+
+    if let Some(card) = app.card {
+        edit_card(bot, card, card_text(db, chat_id, user_id, &app), Some(card_keyboard(chat_id, user_id))).await;
+    }
+    reply(bot, user_id, "✅ Thanks! Your answers were passed on to the admins.").await;
+    true
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Review buttons — `join:<a|d>:<chat_id>:<user_id>`
+//  The presser needs the invite-users right in the group itself.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_review(bot: &Bot, cq_id: &str, presser: i64, presser_name: &str, data: &str, db: &Db, admins: &AdminCache) {
+    let toast = |text: &'static str| {
+        let p = AnswerCallbackQueryParams::new().text(text);
+        bot.answer_callback_query(cq_id, Some(p))
+    };
+    let mut parts = data.splitn(3, ':');
+    let (Some(verb), Some(Ok(chat_id)), Some(Ok(user_id))) = (
+        parts.next(),
+        parts.next().map(str::parse::<i64>),
+        parts.next().map(str::parse::<i64>),
+    ) else { return };
+
+    match admins.lookup(bot, chat_id, presser).await {
+        Ok(m) if m.has(Right::Invite) => {}
+        _ => { let _ = toast("🚫 You need the invite-users right in that group.").await; return; }
+    }
+
+    let key = chat_key(chat_id, user_id);
+    let Some(app) = db.load::<Application>(JOIN_REQUESTS, &key) else {
+        let _ = toast("ℹ️ This request was already handled.").await;
+        return;
+    };
+    db.delete(JOIN_REQUESTS, &key);
+    db.delete(QUESTIONNAIRES, &user_id.to_string());
+
+    let approve = verb == "a";
+    let result = if approve {
+        bot.approve_chat_join_request(chat_id, user_id).await
+    } else {
+        bot.decline_chat_join_request(chat_id, user_id).await
+    };
+
+    let outcome = match (&result, approve) {
+        (Ok(_), true)  => format!("✅ <b>Approved</b> by {}", html::escape(presser_name)),
+        (Ok(_), false) => format!("❌ <b>Declined</b> by {}", html::escape(presser_name)),
+        // Usually the request expired or another admin handled it in Telegram.
+        (Err(e), _)    => format!("⚠️ Could not {}: <code>{}</code>", if approve { "approve" } else { "decline" }, e),
+    };
+    if let Some(card) = app.card {
+        let text = format!("{}\n\n{}", card_text(db, chat_id, user_id, &app), outcome);
+        edit_card(bot, card, text, None).await;
+    }
+    let _ = toast(if result.is_ok() { "Done." } else { "Failed — see the card." }).await;
+
+    if result.is_ok() {
+        let t = Target { id: user_id, name: app.name };
+        let action = if approve { Action::AcceptJoin } else { Action::DeclineJoin };
+        audit::record(bot, db, Entry::new(chat_id, presser, action).target(&t)).await;
+        if approve {
+            reply(bot, user_id, &format!("🎉 Your request to join <b>{}</b> was approved!",
+                html::escape(&chat_title(db, chat_id)))).await;
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /joinpolicy [approve|decline|review]
+//  /joinquestions [q1 | q2 | …|off]
+// ════════════════════════════════════════════════════════════════
+
+fn mode_label(mode: JoinMode) -> &'static str {
+    match mode {
+        JoinMode::Approve => "approve all",
+        JoinMode::Decline => "decline all",
+        JoinMode::Review  => "review by admins",
+    }
+}
+
+pub async fn handle_join_policy(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let mode = match arg.map(str::to_lowercase).as_deref() {
+        None => {
+            let p = settings::load(db, chat_id).join;
+            reply(bot, chat_id, &format!(
+                "🚪 Join requests: <b>{}</b>\n\n\
+                <i>Options:</i> <code>approve</code> · <code>decline</code> · <code>review</code>",
+                mode_label(p.mode))).await;
+            return;
+        }
+        Some("approve") => JoinMode::Approve,
+        Some("decline") => JoinMode::Decline,
+        Some("review")  => JoinMode::Review,
+        Some(_) => {
+            reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/joinpolicy approve|decline|review</code>").await;
+            return;
+        }
+    };
+    if mode == JoinMode::Review && settings::load(db, chat_id).log_channel.is_none() {
+        reply(bot, chat_id,
+            "⚠️ Review cards show applicants' profiles, so they go to a private log channel.\n\n\
+            <i>Set one with</i> <code>/setlog</code> <i>first.</i>").await;
+        return;
+    }
+    settings::update(db, chat_id, |s| s.join.mode = mode);
+    let hint = if mode == JoinMode::Review {
+        "\n\n<i>Cards are posted to the /setlog channel.</i>"
+    } else { "" };
+    reply(bot, chat_id, &format!("✅ Join requests: <b>{}</b>.{}", mode_label(mode), hint)).await;
+}
+
+pub async fn handle_join_questions(bot: &Bot, chat_id: i64, rest: &str, db: &Db) {
+    if rest.is_empty() {
+        let qs = settings::load(db, chat_id).join.questions;
+        let text = if qs.is_empty() {
+            "📝 No questionnaire set.".to_string()
+        } else {
+            let list: Vec<String> = qs.iter().enumerate()
+                .map(|(i, q)| format!("{}. {}", i + 1, html::escape(q))).collect();
+            format!("📝 <b>Questionnaire</b>\n\n{}", list.join("\n"))
+        };
+        reply(bot, chat_id, &format!(
+            "{}\n\n<i>Set with</i> <code>/joinquestions Why join? | How did you find us?</code>\n\
+            <i>Remove with</i> <code>/joinquestions off</code>", text)).await;
+        return;
+    }
+    if rest.eq_ignore_ascii_case("off") {
+        settings::update(db, chat_id, |s| s.join.questions.clear());
+        reply(bot, chat_id, "✅ Questionnaire removed.").await;
+        return;
+    }
+    let questions: Vec<String> = rest.split(['|', '\n'])
+        .map(str::trim).filter(|q| !q.is_empty()).map(str::to_string).collect();
+    if questions.len() > MAX_QUESTIONS {
+        reply(bot, chat_id, &format!("⚠️ At most {} questions.", MAX_QUESTIONS)).await;
+        return;
+    }
+    let n = questions.len();
+    let s = settings::update(db, chat_id, |s| s.join.questions = questions);
+    let note = if s.join.mode != JoinMode::Review {
+        "\n\n<i>Only used with</i> <code>/joinpolicy review</code>."
+    } else { "" };
+    reply(bot, chat_id, &format!("✅ Saved {} question{}.{}", n, if n == 1 { "" } else { "s" }, note)).await;
+}
@@ -0,0 +1,243 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Content Locks
+//  Photos, videos, voice notes and polls are locked through the
+//  chat's default permissions, so Telegram itself blocks them.
+//  Everything else has no permission of its own (stickers, GIFs and
+//  inline results share `can_send_other_messages`) and is deleted
+//  by the bot when a non-admin sends it. `aschannel` deletes posts
+//  made as a channel; the linked channel and anonymous admins are
+//  exempt.
+//  /lock <type…|all>   /unlock <type…|all>   /locks
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::{
+    gen_methods::SetChatPermissionsParams,
+    types::{ChatPermissions, Message, MessageEntity, MessageOrigin},
+    Bot, ChatId,
+};
+
+use super::approvals;
+use super::moderation::all_perms;
+use crate::admin_cache::AdminCache;
+use crate::html::{self, reply};
+use crate::settings::{self, Lock};
+use crate::storage::Db;
+
+const ALL: [Lock; 13] = [
+    Lock::Url, Lock::Forward, Lock::ChannelForward, Lock::Sticker, Lock::Gif, Lock::Photo,
+    Lock::Video, Lock::Voice, Lock::Poll, Lock::Contact, Lock::Inline, Lock::BotMention,
+    Lock::AsChannel,
+];
+
+fn name(lock: Lock) -> &'static str {
+    match lock {
+        Lock::Url            => "url",
+        Lock::Forward        => "forward",
+        Lock::ChannelForward => "channelforward",
+        Lock::Sticker        => "sticker",
+        Lock::Gif            => "gif",
+        Lock::Photo          => "photo",
+        Lock::Video          => "video",
+        Lock::Voice          => "voice",
+        Lock::Poll           => "poll",
+        Lock::Contact        => "contact",
+        Lock::Inline         => "inline",
+        Lock::BotMention     => "botmention",
+        Lock::AsChannel      => "aschannel",
+    }
+}
+
+fn parse(word: &str) -> Option<Lock> {
+    let word = word.to_lowercase();
+    let singular = word.strip_suffix('s').unwrap_or(&word);
+    ALL.into_iter().find(|l| name(*l) == word || name(*l) == singular)
+}
+
+/// The default-permission field that enforces `lock`, if Telegram has one.
+fn permission(perms: &mut ChatPermissions, lock: Lock) -> Option<&mut Option<bool>> {
+    match lock {
+        Lock::Photo => Some(&mut perms.can_send_photos),
+        Lock::Video => Some(&mut perms.can_send_videos),
+        Lock::Voice => Some(&mut perms.can_send_voice_notes),
+        Lock::Poll  => Some(&mut perms.can_send_polls),
+        _ => None,
+    }
+}
+
+fn by_permission(lock: Lock) -> bool {
+    permission(&mut ChatPermissions::default(), lock).is_some()
+}
+
+/// `all_perms()` minus the chat's permission-based locks; what /unro and
+/// the end of a timed /ro restore.
+pub fn member_perms(db: &Db, chat_id: i64) -> ChatPermissions {
+    let mut perms = all_perms();
+    for lock in settings::load(db, chat_id).locks {
+        if let Some(field) = permission(&mut perms, lock) {
+            *field = Some(false);
+        }
+    }
+    perms
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Message inspection — deletion-based locks
+// ════════════════════════════════════════════════════════════════
+
+/// Text covered by an entity; offsets are in UTF-16 code units.
+pub fn entity_text(text: &str, e: &MessageEntity) -> String {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let start = e.offset.max(0) as usize;
+    let end = (e.offset + e.length).max(0) as usize;
+    units.get(start..end).map(String::from_utf16_lossy).unwrap_or_default()
+}
+
+/// Text or caption with its entities.
+fn body(msg: &Message) -> (&str, &[MessageEntity]) {
+    match (&msg.text, &msg.caption) {
+        (Some(t), _) => (t, msg.entities.as_deref().unwrap_or(&[])),
+        (_, Some(c)) => (c, msg.caption_entities.as_deref().unwrap_or(&[])),
+        _ => ("", &[]),
+    }
+}
+
+fn matches(msg: &Message, lock: Lock) -> bool {
+    let (text, entities) = body(msg);
+    match lock {
+        Lock::Url => entities.iter().any(|e| e.r#type == "url" || e.r#type == "text_link"),
+        Lock::Forward => msg.forward_origin.is_some(),
+        Lock::ChannelForward => matches!(msg.forward_origin.as_deref(), Some(MessageOrigin::MessageOriginChannel(_))),
+        Lock::Sticker => msg.sticker.is_some(),
+        Lock::Gif => msg.animation.is_some(),
+        Lock::Photo => msg.photo.is_some(),
+        Lock::Video => msg.video.is_some(),
+        Lock::Voice => msg.voice.is_some(),
+        Lock::Poll => msg.poll.is_some(),
+        Lock::Contact => msg.contact.is_some(),
+        Lock::Inline => msg.via_bot.is_some(),
+        Lock::BotMention => entities.iter().any(|e| match e.r#type.as_str() {
+            "mention"      => entity_text(text, e).to_lowercase().ends_with("bot"),
+            "text_mention" => e.user.as_ref().is_some_and(|u| u.is_bot),
+            _ => false,
+        }),
+        Lock::AsChannel => msg.sender_chat.as_ref().is_some_and(|c| c.id != msg.chat.id),
+    }
+}
+
+/// Deletes `msg` if it breaks one of the chat's deletion-based locks.
+/// Admins, approved users, anonymous admins and the linked channel's
+/// automatic forwards are never touched. Returns true if the message was removed.
+pub async fn check(bot: &Bot, msg: &Message, db: &Db, admins: &AdminCache) -> bool {
+    let chat_id = msg.chat.id;
+    let locks = settings::load(db, chat_id).locks;
+    let Some(hit) = locks.into_iter().filter(|l| !by_permission(*l)).find(|l| matches(msg, *l)) else {
+        return false;
+    };
+    if msg.is_automatic_forward == Some(true) { return false; }
+    if msg.sender_chat.as_ref().is_some_and(|c| c.id == chat_id) { return false; }
+    let Some(from) = &msg.from else { return false };
+    if approvals::exempt(bot, chat_id, from.id, db, admins).await { return false; }
+
+    match bot.delete_message(ChatId::from(chat_id), msg.message_id).await {
+        Ok(_) => {
+            log::debug!("locks: deleted {} from {} in {} ({})", msg.message_id, from.id, chat_id, name(hit));
+            true
+        }
+        Err(e) => {
+            log::warn!("locks: could not delete {} in {}: {}", msg.message_id, chat_id, e);
+            false
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /lock <type…|all>   /unlock <type…|all>
+// ════════════════════════════════════════════════════════════════
+
+fn type_list() -> String {
+    ALL.iter().map(|l| format!("<code>{}</code>", name(*l))).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_args(args: &[&str]) -> Result<Vec<Lock>, String> {
+    if args.iter().any(|a| a.eq_ignore_ascii_case("all")) {
+        return Ok(ALL.to_vec());
+    }
+    args.iter().map(|a| parse(a).ok_or_else(|| a.to_string())).collect()
+}
+
+pub async fn handle_lock(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    set_locks(bot, chat_id, args, db, true).await;
+}
+
+pub async fn handle_unlock(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    set_locks(bot, chat_id, args, db, false).await;
+}
+
+async fn set_locks(bot: &Bot, chat_id: i64, args: &[&str], db: &Db, lock: bool) {
+    let cmd = if lock { "/lock" } else { "/unlock" };
+    let wanted = match parse_args(args) {
+        Ok(w) if !w.is_empty() => w,
+        Ok(_) => {
+            reply(bot, chat_id, &format!("⚠️ <b>Usage:</b> <code>{} &lt;type…|all&gt;</code>\n\n<b>Types:</b> {}", cmd, type_list())).await;
+            return;
+        }
+        Err(bad) => {
+            reply(bot, chat_id, &format!("❌ Unknown lock type <code>{}</code>.\n\n<b>Types:</b> {}", html::escape(&bad), type_list())).await;
+            return;
+        }
+    };
+
+    // Permission-based locks change the chat's default permissions first;
+    // if Telegram refuses, nothing is saved.
+    let mut note = "";
+    if wanted.iter().any(|l| by_permission(*l)) {
+        let mut perms = match bot.get_chat(chat_id).await {
+            Ok(info) => info.permissions.map(|p| *p).unwrap_or_else(all_perms),
+            Err(e) => {
+                reply(bot, chat_id, &format!("❌ Could not read chat permissions: <code>{}</code>", e)).await;
+                return;
+            }
+        };
+        // While /ro is on, unlocking would let media through; the change is
+        // applied when read-only mode is lifted instead.
+        let read_only = perms.can_send_messages == Some(false);
+        if lock || !read_only {
+            for l in &wanted {
+                if let Some(field) = permission(&mut perms, *l) {
+                    *field = Some(!lock);
+                }
+            }
+            let p = SetChatPermissionsParams::new().use_independent_chat_permissions(true);
+            if let Err(e) = bot.set_chat_permissions(ChatId::from(chat_id), perms, Some(p)).await {
+                reply(bot, chat_id, &format!("❌ <b>Failed:</b> <code>{}</code>\n<i>I need the ban-users right.</i>", e)).await;
+                return;
+            }
+        } else {
+            note = "\n\n<i>Read-only mode is on; media permissions return with /unro.</i>";
+        }
+    }
+
+    settings::update(db, chat_id, |s| {
+        s.locks.retain(|l| !wanted.contains(l));
+        if lock { s.locks.extend(wanted.iter().copied()); }
+    });
+    let names: Vec<&str> = wanted.iter().map(|l| name(*l)).collect();
+    let (icon, verb) = if lock { ("🔒", "Locked") } else { ("🔓", "Unlocked") };
+    reply(bot, chat_id, &format!("{} <b>{}:</b> {}{}", icon, verb, names.join(", "), note)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /locks — current state
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_locks(bot: &Bot, chat_id: i64, db: &Db) {
+    let locks = settings::load(db, chat_id).locks;
+    let mut text = String::from("🔐 <b>Locks</b>\n");
+    for l in ALL {
+        let state = if locks.contains(&l) { "🔒" } else { "🔓" };
+        let how = if by_permission(l) { "permission" } else { "deleted" };
+        text.push_str(&format!("\n{} <code>{}</code> <i>({})</i>", state, name(l), how));
+    }
+    text.push_str("\n\n<i>Use</i> <code>/lock &lt;type&gt;</code> <i>or</i> <code>/unlock &lt;type&gt;</code><i>. Admins are never affected.</i>");
+    reply(bot, chat_id, &text).await;
+}
@@ -1,8 +1,23 @@
 pub mod admin;
+pub mod anon_admin;
+pub mod antiflood;
+pub mod approvals;
+pub mod antiraid;
+pub mod antispam;
+pub mod audit;
+pub mod blocklist;
 pub mod broadcast;
 pub mod callbacks;
+pub mod captcha;
 pub mod commands;
 pub mod filters;
+pub mod gbans;
+pub mod greetings;
 pub mod inline;
+pub mod join_requests;
+pub mod locks;
 pub mod moderation;
 pub mod notes;
+pub mod reports;
+pub mod system;
+pub mod warns;
@@ -1,36 +1,36 @@
 // ════════════════════════════════════════════════════════════════
 //  Rustace Bot — Moderation Handlers
-//  Commands: ban, unban, kick, mute, unmute, warn, unwarn, delete,
-//            pin, unpin, ro, unro
-//  All commands work by replying to the target user's message.
+//  Commands: ban, unban, kick, mute, restrict, unmute, delete, purge,
+//            spurge, pin, unpin, ro, unro, pending  (warnings: warns.rs)
+//  Timed actions are queued on the scheduler (scheduler.rs).
+//  Targets come from targets::resolve — reply, ID, @username or mention.
+//  /ban and /unban also take channels that post in the chat.
 // ════════════════════════════════════════════════════════════════
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-
 use tgbotrs::{
     gen_methods::{
         BanChatMemberParams, RestrictChatMemberParams, SendMessageParams,
         UnbanChatMemberParams,
     },
-    types::{ChatPermissions, InlineKeyboardMarkup},
+    types::{ChatPermissions, InlineKeyboardMarkup, Message},
     Bot, ChatId, ReplyMarkup,
 };
 
+use super::audit::{self, Action, Entry};
 use super::commands::btn;
-
-// ── Warn store (in-memory, per chat:user) ────────────────────────────────────
-//   Resets on bot restart — good enough for most bots.
-
-pub type WarnStore = Arc<Mutex<HashMap<(i64, i64), u8>>>;
-
-pub fn new_warn_store() -> WarnStore {
-    Arc::new(Mutex::new(HashMap::new()))
-}
+use super::locks;
+use crate::admin_cache::AdminCache;
+use crate::duration::{self, DurationError};
+use crate::html;
+use crate::permissions;
+use crate::registry::{self, Category};
+use crate::scheduler::{Job, Scheduler};
+use crate::storage::Db;
+use crate::targets::{self, Lookup, Resolved, Target};
 
 // ── Permission helpers ───────────────────────────────────────────────────────
 
-fn no_perms() -> ChatPermissions {
+pub fn no_perms() -> ChatPermissions {
     ChatPermissions {
         can_send_messages: Some(false),
         can_send_audios: Some(false),
@@ -46,10 +46,11 @@ fn no_perms() -> ChatPermissions {
         can_invite_users: Some(false),
         can_pin_messages: Some(false),
         can_manage_topics: Some(false),
+        ..Default::default()
     }
 }
 
-fn all_perms() -> ChatPermissions {
+pub fn all_perms() -> ChatPermissions {
     ChatPermissions {
         can_send_messages: Some(true),
         can_send_audios: Some(true),
@@ -65,9 +66,86 @@ fn all_perms() -> ChatPermissions {
         can_invite_users: Some(true),
         can_pin_messages: Some(false),
         can_manage_topics: Some(false),
+        ..Default::default()
     }
 }
 
+/// What an ordinary member may do in the chat: its default permissions
+/// from `get_chat`. While /ro is on those are empty, so what /unro will
+/// restore is used instead; likewise when the chat can't be read.
+pub async fn default_perms(bot: &Bot, chat_id: i64, db: &Db) -> ChatPermissions {
+    match bot.get_chat(chat_id).await {
+        Ok(info) => match info.permissions {
+            Some(p) if p.can_send_messages != Some(false) => *p,
+            _ => locks::member_perms(db, chat_id),
+        },
+        Err(e) => {
+            log::warn!("could not read default permissions of {}: {}", chat_id, e);
+            locks::member_perms(db, chat_id)
+        }
+    }
+}
+
+// ── Restriction presets for /restrict ────────────────────────────────────────
+//   A spec is a comma-separated list of presets and permission names,
+//   e.g. "media", "stickers,polls" or "photos,videos". Each entry names
+//   what the member loses; everything else stays at the chat default.
+
+const PRESETS: [(&str, &[&str]); 4] = [
+    ("media",    &["audios", "documents", "photos", "videos", "videonotes", "voicenotes"]),
+    ("links",    &["previews"]),
+    ("stickers", &["other"]),
+    ("polls",    &["polls"]),
+];
+
+const SEND_PERMS: [&str; 10] = [
+    "messages", "audios", "documents", "photos", "videos",
+    "videonotes", "voicenotes", "polls", "other", "previews",
+];
+
+/// The permission field behind a name from `SEND_PERMS`.
+fn send_perm<'a>(perms: &'a mut ChatPermissions, name: &str) -> Option<&'a mut Option<bool>> {
+    match name {
+        "messages"   => Some(&mut perms.can_send_messages),
+        "audios"     => Some(&mut perms.can_send_audios),
+        "documents"  => Some(&mut perms.can_send_documents),
+        "photos"     => Some(&mut perms.can_send_photos),
+        "videos"     => Some(&mut perms.can_send_videos),
+        "videonotes" => Some(&mut perms.can_send_video_notes),
+        "voicenotes" => Some(&mut perms.can_send_voice_notes),
+        "polls"      => Some(&mut perms.can_send_polls),
+        "other"      => Some(&mut perms.can_send_other_messages),
+        "previews"   => Some(&mut perms.can_add_web_page_previews),
+        _ => None,
+    }
+}
+
+/// Expands a spec into permission names; `Err` carries the unknown entry.
+fn parse_restriction(spec: &str) -> Result<Vec<&'static str>, String> {
+    let mut names: Vec<&'static str> = Vec::new();
+    for word in spec.split(',').map(|w| w.trim().to_lowercase()).filter(|w| !w.is_empty()) {
+        // "photo" works as well as "photos".
+        let is = |name: &str| name == word || name.strip_suffix('s') == Some(word.as_str());
+        let expanded: &[&'static str] = match PRESETS.iter().find(|(p, _)| is(p)) {
+            Some((_, perms)) => perms,
+            None => match SEND_PERMS.iter().find(|p| is(p)) {
+                Some(p) => std::slice::from_ref(p),
+                None => return Err(word),
+            },
+        };
+        for p in expanded {
+            if !names.contains(p) { names.push(p); }
+        }
+    }
+    Ok(names)
+}
+
+fn restriction_options() -> String {
+    let presets: Vec<String> = PRESETS.iter().map(|(p, _)| format!("<code>{}</code>", p)).collect();
+    let perms: Vec<String> = SEND_PERMS.iter().map(|p| format!("<code>{}</code>", p)).collect();
+    format!("<b>Presets:</b> {}\n<b>Permissions:</b> {}", presets.join(" "), perms.join(" "))
+}
+
 // ── Reply helper: sends a moderation result message ──────────────────────────
 
 async fn mod_msg(bot: &Bot, chat_id: i64, text: &str, back_cb: &str) {
@@ -80,100 +158,159 @@ async fn mod_msg(bot: &Bot, chat_id: i64, text: &str, back_cb: &str) {
     let _ = bot.send_message(chat_id, text, Some(p)).await;
 }
 
-// ── Parse duration arg like "1h", "30m", "7d" → unix timestamp ──────────────
+// ── Duration + reason from the text after the target ─────────────────────────
+//   "/ban @user 7d spamming" → (Some(604800), "spamming")
+//   A first word that looks like a duration but doesn't parse is an error,
+//   never a reason — "/ban @user 1x" must not ban forever.
 
-fn parse_duration_secs(arg: Option<&str>) -> Option<i64> {
-    let s = arg?;
-    let (num_str, unit) = if s.ends_with('d') {
-        (&s[..s.len()-1], 86400i64)
-    } else if s.ends_with('h') {
-        (&s[..s.len()-1], 3600i64)
-    } else if s.ends_with('m') {
-        (&s[..s.len()-1], 60i64)
-    } else {
-        return None;
-    };
-    let n: i64 = num_str.parse().ok()?;
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
-    Some(now + n * unit)
+fn split_duration(rest: &str) -> Result<(Option<i64>, &str), (String, DurationError)> {
+    let first = rest.split_whitespace().next().unwrap_or("");
+    if !duration::looks_like(first) {
+        return Ok((None, rest));
+    }
+    match duration::parse_restriction(first) {
+        Ok(secs) => Ok((Some(secs), rest[first.len()..].trim())),
+        Err(e)   => Err((first.to_string(), e)),
+    }
+}
+
+async fn duration_error(bot: &Bot, chat_id: i64, word: &str, err: DurationError) {
+    mod_msg(bot, chat_id,
+        &format!("⚠️ <b>Invalid duration</b> <code>{}</code>: {}\n\nExamples: {}",
+            html::escape(word), err, duration::EXAMPLES),
+        "main_menu").await;
+}
+
+/// `Ok(None)` without an argument; replies and returns `Err` when it doesn't parse.
+async fn optional_duration(bot: &Bot, chat_id: i64, arg: Option<&str>) -> Result<Option<i64>, ()> {
+    match arg {
+        None => Ok(None),
+        Some(a) => match duration::parse(a) {
+            Ok(secs) => Ok(Some(secs)),
+            Err(e) => {
+                duration_error(bot, chat_id, a, e).await;
+                Err(())
+            }
+        },
+    }
+}
+
+fn reason_line(reason: &str) -> String {
+    if reason.is_empty() { String::new() } else { format!("\n<b>Reason:</b> {}", html::escape(reason)) }
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /ban [duration] — Reply to a message to ban that user
-//  Duration examples: /ban 7d  /ban 2h  /ban 30m  (omit = forever)
+//  /ban <user> [duration] [reason]
+//  Duration examples: /ban 7d  /ban 1d12h  /ban PT30M  (omit = forever)
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_ban(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-    arg: Option<&str>,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
+pub async fn handle_ban(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    if let Lookup::SenderChat(c) = &target.target {
+        if c.id != chat_id { return ban_sender_chat(bot, chat_id, by, c, &target.rest, db).await; }
+    }
+    let Some(t) = targets::require(bot, chat_id, target, "/ban", "[duration] [reason]").await else { return };
+    if !permissions::guard_target(bot, admins, chat_id, t.id, false).await { return; }
+
+    let (secs, reason) = match split_duration(&target.rest) {
+        Ok(split) => split,
+        Err((word, e)) => return duration_error(bot, chat_id, &word, e).await,
+    };
+    let mut params = BanChatMemberParams::new().revoke_messages(true);
+    let duration_label = match secs {
+        Some(secs) => {
+            params = params.until_date(duration::now() + secs);
+            format!("for <b>{}</b>", duration::fmt(secs))
+        }
+        None => "<b>permanently</b>".to_string(),
+    };
+
+    match bot.ban_chat_member(ChatId::from(chat_id), t.id, Some(params)).await {
+        Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Ban)
+                .target(t).duration(secs).reason(reason)).await;
+            let job = Job::BanExpired { user_id: t.id, name: t.name.clone() };
+            match secs {
+                Some(secs) => sched.schedule(chat_id, secs, job),
+                None => sched.cancel(chat_id, &job),
+            }
             mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/ban</code> or <code>/ban 7d</code>",
+                &format!("🔨 <b>Banned</b> <a href=\"tg://user?id={}\">{}</a> {}{}\n\n<i>Messages revoked.</i>",
+                    t.id, html::escape(&t.name), duration_label, reason_line(reason)),
                 "main_menu").await;
-            return;
         }
-    };
+        Err(e) => {
+            mod_msg(bot, chat_id,
+                &format!("❌ <b>Ban failed:</b> <code>{}</code>\n\n<i>Bot must be admin with ban rights.</i>", e),
+                "main_menu").await;
+        }
+    }
+}
 
-    let mut params = BanChatMemberParams::new().revoke_messages(true);
-    let duration_label;
+// ════════════════════════════════════════════════════════════════
+//  /unban <user>
+// ════════════════════════════════════════════════════════════════
 
-    if let Some(until) = parse_duration_secs(arg) {
-        params = params.until_date(until);
-        duration_label = format!("for <b>{}</b>", arg.unwrap_or("?"));
-    } else {
-        duration_label = "<b>permanently</b>".to_string();
+pub async fn handle_unban(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, sched: &Scheduler) {
+    if let Lookup::SenderChat(c) = &target.target {
+        if c.id != chat_id { return unban_sender_chat(bot, chat_id, by, c, db).await; }
     }
+    let Some(t) = targets::require(bot, chat_id, target, "/unban", "").await else { return };
 
-    match bot.ban_chat_member(ChatId::from(chat_id), target_id, Some(params)).await {
+    let params = UnbanChatMemberParams::new().only_if_banned(true);
+    match bot.unban_chat_member(ChatId::from(chat_id), t.id, Some(params)).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Unban).target(t)).await;
+            sched.cancel(chat_id, &Job::BanExpired { user_id: t.id, name: t.name.clone() });
             mod_msg(bot, chat_id,
-                &format!("🔨 <b>Banned</b> <a href=\"tg://user?id={}\">{}</a> {}\n\n<i>Messages revoked.</i>",
-                    target_id, html_escape(&target_name), duration_label),
+                &format!("✅ <b>Unbanned</b> <a href=\"tg://user?id={}\">{}</a>\n\n<i>User can now rejoin via invite link.</i>",
+                    t.id, html::escape(&t.name)),
                 "main_menu").await;
         }
         Err(e) => {
             mod_msg(bot, chat_id,
-                &format!("❌ <b>Ban failed:</b> <code>{}</code>\n\n<i>Bot must be admin with ban rights.</i>", e),
+                &format!("❌ <b>Unban failed:</b> <code>{}</code>", e),
                 "main_menu").await;
         }
     }
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /unban — Reply to a message to unban that user
+//  Channels — /ban and /unban aimed at a chat posting as itself
+//  A banned channel can't post here, and neither can its owner
+//  while posting as it. There is no timed variant.
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_unban(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
-            mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/unban</code>",
-                "main_menu").await;
+async fn ban_sender_chat(bot: &Bot, chat_id: i64, by: i64, c: &Target, rest: &str, db: &Db) {
+    let reason = match split_duration(rest) {
+        Ok((None, reason)) => reason,
+        Ok((Some(_), _)) => {
+            mod_msg(bot, chat_id, "⚠️ Channel bans can't be timed. Use /unban to lift one.", "main_menu").await;
             return;
         }
+        Err((word, e)) => return duration_error(bot, chat_id, &word, e).await,
     };
+    match bot.ban_chat_sender_chat(ChatId::from(chat_id), c.id).await {
+        Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Ban).target(c).reason(reason)).await;
+            mod_msg(bot, chat_id,
+                &format!("🔨 <b>Banned channel</b> {} (<code>{}</code>){}\n\n<i>Nobody can post here as this channel any more.</i>",
+                    html::escape(&c.name), c.id, reason_line(reason)),
+                "main_menu").await;
+        }
+        Err(e) => {
+            mod_msg(bot, chat_id,
+                &format!("❌ <b>Ban failed:</b> <code>{}</code>\n\n<i>Bot must be admin with ban rights.</i>", e),
+                "main_menu").await;
+        }
+    }
+}
 
-    let params = UnbanChatMemberParams::new().only_if_banned(true);
-    match bot.unban_chat_member(ChatId::from(chat_id), target_id, Some(params)).await {
+async fn unban_sender_chat(bot: &Bot, chat_id: i64, by: i64, c: &Target, db: &Db) {
+    match bot.unban_chat_sender_chat(ChatId::from(chat_id), c.id).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Unban).target(c)).await;
             mod_msg(bot, chat_id,
-                &format!("✅ <b>Unbanned</b> <a href=\"tg://user?id={}\">{}</a>\n\n<i>User can now rejoin via invite link.</i>",
-                    target_id, html_escape(&target_name)),
+                &format!("✅ <b>Unbanned channel</b> {} (<code>{}</code>)", html::escape(&c.name), c.id),
                 "main_menu").await;
         }
         Err(e) => {
@@ -185,31 +322,20 @@ pub async fn handle_unban(
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /kick — Ban then immediately unban (removes from group, can rejoin)
+//  /kick <user> [reason] — Ban then immediately unban (can rejoin)
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_kick(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
-            mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/kick</code>",
-                "main_menu").await;
-            return;
-        }
-    };
+pub async fn handle_kick(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/kick", "[reason]").await else { return };
+    if !permissions::guard_target(bot, admins, chat_id, t.id, false).await { return; }
 
-    let ban_ok = bot.ban_chat_member(ChatId::from(chat_id), target_id, None).await.is_ok();
+    let ban_ok = bot.ban_chat_member(ChatId::from(chat_id), t.id, None).await.is_ok();
     if ban_ok {
-        let _ = bot.unban_chat_member(ChatId::from(chat_id), target_id, None).await;
+        let _ = bot.unban_chat_member(ChatId::from(chat_id), t.id, None).await;
+        audit::record(bot, db, Entry::new(chat_id, by, Action::Kick).target(t).reason(&target.rest)).await;
         mod_msg(bot, chat_id,
-            &format!("👢 <b>Kicked</b> <a href=\"tg://user?id={}\">{}</a>\n\n<i>They were removed but can rejoin via invite link.</i>",
-                target_id, html_escape(&target_name)),
+            &format!("👢 <b>Kicked</b> <a href=\"tg://user?id={}\">{}</a>{}\n\n<i>They were removed but can rejoin via invite link.</i>",
+                t.id, html::escape(&t.name), reason_line(&target.rest)),
             "main_menu").await;
     } else {
         mod_msg(bot, chat_id,
@@ -219,42 +345,39 @@ pub async fn handle_kick(
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /mute [duration] — Restrict all permissions
-//  Duration: /mute 1h  /mute 30m  /mute 7d  (omit = forever)
+//  /mute <user> [duration] [reason] — Restrict all permissions
+//  Duration: /mute 1h  /mute 90s  /mute 1w  (omit = forever)
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_mute(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-    arg: Option<&str>,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
-            mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/mute</code> or <code>/mute 1h</code>",
-                "main_menu").await;
-            return;
-        }
-    };
+pub async fn handle_mute(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/mute", "[duration] [reason]").await else { return };
+    if !permissions::guard_target(bot, admins, chat_id, t.id, false).await { return; }
 
+    let (secs, reason) = match split_duration(&target.rest) {
+        Ok(split) => split,
+        Err((word, e)) => return duration_error(bot, chat_id, &word, e).await,
+    };
     let mut params = RestrictChatMemberParams::new();
-    let duration_label;
-
-    if let Some(until) = parse_duration_secs(arg) {
-        params = params.until_date(until);
-        duration_label = format!("for <b>{}</b>", arg.unwrap_or("?"));
-    } else {
-        duration_label = "<b>permanently</b>".to_string();
-    }
+    let duration_label = match secs {
+        Some(secs) => {
+            params = params.until_date(duration::now() + secs);
+            format!("for <b>{}</b>", duration::fmt(secs))
+        }
+        None => "<b>permanently</b>".to_string(),
+    };
 
-    match bot.restrict_chat_member(ChatId::from(chat_id), target_id, no_perms(), Some(params)).await {
+    match bot.restrict_chat_member(ChatId::from(chat_id), t.id, no_perms(), Some(params)).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Mute)
+                .target(t).duration(secs).reason(reason)).await;
+            let job = Job::MuteExpired { user_id: t.id, name: t.name.clone() };
+            match secs {
+                Some(secs) => sched.schedule(chat_id, secs, job),
+                None => sched.cancel(chat_id, &job),
+            }
             mod_msg(bot, chat_id,
-                &format!("🔇 <b>Muted</b> <a href=\"tg://user?id={}\">{}</a> {}\n\n<i>All send permissions removed.</i>",
-                    target_id, html_escape(&target_name), duration_label),
+                &format!("🔇 <b>Muted</b> <a href=\"tg://user?id={}\">{}</a> {}{}\n\n<i>All send permissions removed.</i>",
+                    t.id, html::escape(&t.name), duration_label, reason_line(reason)),
                 "main_menu").await;
         }
         Err(e) => {
@@ -266,154 +389,99 @@ pub async fn handle_mute(
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /unmute — Restore all standard permissions
+//  /restrict <user> <preset|perm,…> [duration] [reason]
+//  Takes away part of what members may send, e.g. /restrict media 1d
+//  or /restrict stickers,polls. Also lifted with /unmute.
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_unmute(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
-            mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/unmute</code>",
-                "main_menu").await;
+pub async fn handle_restrict(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    const USAGE: &str = "<preset|perm,…> [duration] [reason]";
+    let Some(t) = targets::require(bot, chat_id, target, "/restrict", USAGE).await else { return };
+    if !permissions::guard_target(bot, admins, chat_id, t.id, false).await { return; }
+
+    let rest = target.rest.trim();
+    let spec = rest.split_whitespace().next().unwrap_or("");
+    let removed = match parse_restriction(spec) {
+        Ok(names) if !names.is_empty() => names,
+        Ok(_) => {
+            mod_msg(bot, chat_id, &format!(
+                "⚠️ <b>Usage:</b> <code>/restrict &lt;user&gt; &lt;preset|perm,…&gt; [duration] [reason]</code>\n\n{}",
+                restriction_options()), "main_menu").await;
+            return;
+        }
+        Err(bad) => {
+            mod_msg(bot, chat_id, &format!("❌ Unknown preset or permission <code>{}</code>.\n\n{}",
+                html::escape(&bad), restriction_options()), "main_menu").await;
             return;
         }
     };
+    let (secs, reason) = match split_duration(rest[spec.len()..].trim()) {
+        Ok(split) => split,
+        Err((word, e)) => return duration_error(bot, chat_id, &word, e).await,
+    };
+
+    let mut perms = default_perms(bot, chat_id, db).await;
+    for name in &removed {
+        if let Some(field) = send_perm(&mut perms, name) { *field = Some(false); }
+    }
+    let mut params = RestrictChatMemberParams::new().use_independent_chat_permissions(true);
+    let duration_label = match secs {
+        Some(secs) => {
+            params = params.until_date(duration::now() + secs);
+            format!("for <b>{}</b>", duration::fmt(secs))
+        }
+        None => "<b>permanently</b>".to_string(),
+    };
 
-    match bot.restrict_chat_member(ChatId::from(chat_id), target_id, all_perms(), None).await {
+    match bot.restrict_chat_member(ChatId::from(chat_id), t.id, perms, Some(params)).await {
         Ok(_) => {
+            let what = removed.join(", ");
+            let logged = if reason.is_empty() { format!("no {}", what) } else { format!("no {}; {}", what, reason) };
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Restrict)
+                .target(t).duration(secs).reason(&logged)).await;
+            let job = Job::MuteExpired { user_id: t.id, name: t.name.clone() };
+            match secs {
+                Some(secs) => sched.schedule(chat_id, secs, job),
+                None => sched.cancel(chat_id, &job),
+            }
             mod_msg(bot, chat_id,
-                &format!("🔊 <b>Unmuted</b> <a href=\"tg://user?id={}\">{}</a>\n\n<i>Standard permissions restored.</i>",
-                    target_id, html_escape(&target_name)),
+                &format!("⛓ <b>Restricted</b> <a href=\"tg://user?id={}\">{}</a> {}{}\n\n<i>Can no longer send:</i> {}",
+                    t.id, html::escape(&t.name), duration_label, reason_line(reason), what),
                 "main_menu").await;
         }
         Err(e) => {
             mod_msg(bot, chat_id,
-                &format!("❌ <b>Unmute failed:</b> <code>{}</code>", e),
+                &format!("❌ <b>Restrict failed:</b> <code>{}</code>\n\n<i>Bot must be admin with restrict rights.</i>", e),
                 "main_menu").await;
         }
     }
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /warn — Warn a user. At 3 warnings → auto-ban.
+//  /unmute <user> — Back to the chat's default permissions
+//  Lifts /mute and /restrict alike.
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_warn(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-    warn_store: &WarnStore,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
-            mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/warn</code>",
-                "main_menu").await;
-            return;
-        }
-    };
-
-    let count = {
-        let mut store = warn_store.lock().unwrap();
-        let entry = store.entry((chat_id, target_id)).or_insert(0);
-        *entry += 1;
-        *entry
-    };
-
-    if count >= 3 {
-        // Auto-ban at 3 warnings
-        {
-            let mut store = warn_store.lock().unwrap();
-            store.remove(&(chat_id, target_id));
-        }
-        let _ = bot.ban_chat_member(ChatId::from(chat_id), target_id,
-            Some(BanChatMemberParams::new().revoke_messages(true))).await;
-        mod_msg(bot, chat_id,
-            &format!("🔨 <a href=\"tg://user?id={}\">{}</a> reached <b>3/3 warnings</b> and was automatically <b>banned</b>.",
-                target_id, html_escape(&target_name)),
-            "main_menu").await;
-    } else {
-        let bars = "⚠️".repeat(count as usize) + &"▪️".repeat(3 - count as usize);
-        mod_msg(bot, chat_id,
-            &format!("⚠️ <b>Warning {}/3</b> issued to <a href=\"tg://user?id={}\">{}</a>\n\n{}\n\n<i>3 warnings = auto-ban.</i>",
-                count, target_id, html_escape(&target_name), bars),
-            "main_menu").await;
-    }
-}
-
-// ════════════════════════════════════════════════════════════════
-//  /unwarn — Remove one warning from a user
-// ════════════════════════════════════════════════════════════════
+pub async fn handle_unmute(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, sched: &Scheduler) {
+    let Some(t) = targets::require(bot, chat_id, target, "/unmute", "").await else { return };
 
-pub async fn handle_unwarn(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-    warn_store: &WarnStore,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
+    let perms = default_perms(bot, chat_id, db).await;
+    let params = RestrictChatMemberParams::new().use_independent_chat_permissions(true);
+    match bot.restrict_chat_member(ChatId::from(chat_id), t.id, perms, Some(params)).await {
+        Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Unmute).target(t)).await;
+            sched.cancel(chat_id, &Job::MuteExpired { user_id: t.id, name: t.name.clone() });
             mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/unwarn</code>",
+                &format!("🔊 <b>Unmuted</b> <a href=\"tg://user?id={}\">{}</a>\n\n<i>The chat's default permissions apply again.</i>",
+                    t.id, html::escape(&t.name)),
                 "main_menu").await;
-            return;
         }
-    };
-
-    let count = {
-        let mut store = warn_store.lock().unwrap();
-        let entry = store.entry((chat_id, target_id)).or_insert(0);
-        if *entry > 0 { *entry -= 1; }
-        *entry
-    };
-
-    mod_msg(bot, chat_id,
-        &format!("✅ Warning removed from <a href=\"tg://user?id={}\">{}</a>\n\nCurrent warnings: <b>{}/3</b>",
-            target_id, html_escape(&target_name), count),
-        "main_menu").await;
-}
-
-// ════════════════════════════════════════════════════════════════
-//  /warns — Check how many warnings a user has
-// ════════════════════════════════════════════════════════════════
-
-pub async fn handle_warns(
-    bot: &Bot,
-    chat_id: i64,
-    reply_user_id: Option<i64>,
-    reply_user_name: Option<&str>,
-    warn_store: &WarnStore,
-) {
-    let (target_id, target_name) = match (reply_user_id, reply_user_name) {
-        (Some(id), Some(name)) => (id, name.to_string()),
-        _ => {
+        Err(e) => {
             mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/warns</code>",
+                &format!("❌ <b>Unmute failed:</b> <code>{}</code>", e),
                 "main_menu").await;
-            return;
         }
-    };
-
-    let count = {
-        let store = warn_store.lock().unwrap();
-        *store.get(&(chat_id, target_id)).unwrap_or(&0)
-    };
-
-    let bars = "⚠️".repeat(count as usize) + &"▪️".repeat(3 - count.min(3) as usize);
-    mod_msg(bot, chat_id,
-        &format!("📋 <b>Warnings for</b> <a href=\"tg://user?id={}\">{}</a>: <b>{}/3</b>\n\n{}",
-            target_id, html_escape(&target_name), count, bars),
-        "main_menu").await;
+    }
 }
 
 // ════════════════════════════════════════════════════════════════
@@ -423,8 +491,11 @@ pub async fn handle_warns(
 pub async fn handle_delete(
     bot: &Bot,
     chat_id: i64,
+    by: i64,
     reply_message_id: Option<i64>,
     command_message_id: i64,
+    target: &Resolved,
+    db: &Db,
 ) {
     // Delete the command message itself
     let _ = bot.delete_message(ChatId::from(chat_id), command_message_id).await;
@@ -432,7 +503,12 @@ pub async fn handle_delete(
     match reply_message_id {
         Some(mid) => {
             match bot.delete_message(ChatId::from(chat_id), mid).await {
-                Ok(_) => {} // silent success
+                Ok(_) => {
+                    // Silent in the chat; the author is the resolved target.
+                    let mut entry = Entry::new(chat_id, by, Action::Delete);
+                    if let Lookup::Found(t) = &target.target { entry = entry.target(t); }
+                    audit::record(bot, db, entry).await;
+                }
                 Err(e) => {
                     mod_msg(bot, chat_id,
                         &format!("❌ <b>Delete failed:</b> <code>{}</code>", e),
@@ -449,32 +525,129 @@ pub async fn handle_delete(
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /pin — Pin the replied-to message
+//  /purge — Delete from the replied-to message up to the command
+//  /purge N — Delete the last N messages
+//  /spurge — Same, without the notice
+//  Telegram only lets bots delete messages younger than 48 hours and
+//  takes at most 100 ids per delete_messages call.
+// ════════════════════════════════════════════════════════════════
+
+const PURGE_CHUNK: usize = 100;
+const PURGE_MAX: i64 = 5000;
+const DELETE_WINDOW: i64 = 48 * duration::HOUR;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_purge(
+    bot: &Bot,
+    chat_id: i64,
+    by: i64,
+    reply: Option<&Message>,
+    command_message_id: i64,
+    arg: Option<&str>,
+    silent: bool,
+    db: &Db,
+) {
+    let cmd = if silent { "/spurge" } else { "/purge" };
+    let first = match (reply, arg) {
+        (Some(r), _) if duration::now() - r.date >= DELETE_WINDOW => {
+            mod_msg(bot, chat_id,
+                "⚠️ That message is older than 48 hours — Telegram won't let bots delete it.\n\
+                <i>Reply to a newer message, or use</i> <code>/purge N</code>.",
+                "main_menu").await;
+            return;
+        }
+        (Some(r), _) => r.message_id,
+        (None, Some(n)) => match n.parse::<i64>() {
+            Ok(n) if (1..=PURGE_MAX).contains(&n) => command_message_id - n,
+            _ => {
+                mod_msg(bot, chat_id, &format!("⚠️ N must be a number from 1 to {}.", PURGE_MAX), "main_menu").await;
+                return;
+            }
+        },
+        (None, None) => {
+            mod_msg(bot, chat_id, &format!(
+                "⚠️ <b>Usage:</b> reply to the first message to remove with <code>{0}</code>, \
+                or use <code>{0} N</code> for the last N messages.", cmd), "main_menu").await;
+            return;
+        }
+    };
+    if command_message_id - first > PURGE_MAX {
+        mod_msg(bot, chat_id, &format!("⚠️ That's more than {} messages — purge in smaller steps.", PURGE_MAX), "main_menu").await;
+        return;
+    }
+
+    // The command goes in the last batch so it disappears with the rest.
+    // deleteMessages silently skips IDs it can't delete (gone, service
+    // messages, older than 48h), so only the range is known, not a count.
+    let first = first.max(1);
+    let ids: Vec<i64> = (first..=command_message_id).collect();
+    let mut purged_any = false;
+    let mut failure = None;
+    for chunk in ids.chunks(PURGE_CHUNK) {
+        match bot.delete_messages(ChatId::from(chat_id), chunk.to_vec()).await {
+            Ok(_) => purged_any = true,
+            Err(e) => failure = Some(e),
+        }
+    }
+
+    let range = format!("{}–{}", first, command_message_id - 1);
+    if purged_any {
+        audit::record(bot, db, Entry::new(chat_id, by, Action::Delete)
+            .reason(&format!("purged messages {}", range))).await;
+    }
+    match failure {
+        Some(e) if !purged_any => mod_msg(bot, chat_id,
+            &format!("❌ <b>Purge failed:</b> <code>{}</code>", e), "main_menu").await,
+        _ if silent => {}
+        Some(e) => mod_msg(bot, chat_id, &format!(
+            "🧹 <b>Purged messages {}</b>, but some batches failed: <code>{}</code>", range, e), "main_menu").await,
+        None => mod_msg(bot, chat_id, &format!(
+            "🧹 <b>Purged messages {}</b>\n\n\
+            <i>Messages older than 48 hours are skipped by Telegram.</i>", range),
+            "main_menu").await,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /pin [duration] — Pin the replied-to message, optionally for a while
 // ════════════════════════════════════════════════════════════════
 
 pub async fn handle_pin(
     bot: &Bot,
     chat_id: i64,
+    by: i64,
     reply_message_id: Option<i64>,
+    arg: Option<&str>,
+    db: &Db,
+    sched: &Scheduler,
 ) {
-    match reply_message_id {
-        Some(mid) => {
-            match bot.pin_chat_message(ChatId::from(chat_id), mid, None).await {
-                Ok(_) => {
-                    mod_msg(bot, chat_id,
-                        "📌 <b>Message pinned!</b>",
-                        "main_menu").await;
+    let Some(mid) = reply_message_id else {
+        mod_msg(bot, chat_id,
+            "⚠️ <b>Usage:</b> Reply to a message with <code>/pin [duration]</code>",
+            "main_menu").await;
+        return;
+    };
+    let Ok(secs) = optional_duration(bot, chat_id, arg).await else { return };
+
+    match bot.pin_chat_message(ChatId::from(chat_id), mid, None).await {
+        Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Pin).duration(secs)).await;
+            let job = Job::Unpin { message_id: mid };
+            let text = match secs {
+                Some(s) => {
+                    sched.schedule(chat_id, s, job);
+                    format!("📌 <b>Message pinned</b> for <b>{}</b>.", duration::fmt(s))
                 }
-                Err(e) => {
-                    mod_msg(bot, chat_id,
-                        &format!("❌ <b>Pin failed:</b> <code>{}</code>\n\n<i>Bot must be admin with pin rights.</i>", e),
-                        "main_menu").await;
+                None => {
+                    sched.cancel(chat_id, &job);
+                    "📌 <b>Message pinned!</b>".to_string()
                 }
-            }
+            };
+            mod_msg(bot, chat_id, &text, "main_menu").await;
         }
-        None => {
+        Err(e) => {
             mod_msg(bot, chat_id,
-                "⚠️ <b>Usage:</b> Reply to a message with <code>/pin</code>",
+                &format!("❌ <b>Pin failed:</b> <code>{}</code>\n\n<i>Bot must be admin with pin rights.</i>", e),
                 "main_menu").await;
         }
     }
@@ -484,9 +657,10 @@ pub async fn handle_pin(
 //  /unpin — Unpin the current pinned message
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_unpin(bot: &Bot, chat_id: i64) {
+pub async fn handle_unpin(bot: &Bot, chat_id: i64, by: i64, db: &Db) {
     match bot.unpin_chat_message(ChatId::from(chat_id), None).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Unpin)).await;
             mod_msg(bot, chat_id, "📌 <b>Message unpinned!</b>", "main_menu").await;
         }
         Err(e) => {
@@ -498,16 +672,30 @@ pub async fn handle_unpin(bot: &Bot, chat_id: i64) {
 }
 
 // ════════════════════════════════════════════════════════════════
-//  /ro — Set chat to read-only (mute everyone)
+//  /ro [duration] — Set chat to read-only (mute everyone)
 //  /unro — Restore normal chat permissions
+//  With a duration the scheduler lifts read-only mode by itself.
 // ════════════════════════════════════════════════════════════════
 
-pub async fn handle_ro(bot: &Bot, chat_id: i64) {
+pub async fn handle_ro(bot: &Bot, chat_id: i64, by: i64, arg: Option<&str>, db: &Db, sched: &Scheduler) {
+    let Ok(secs) = optional_duration(bot, chat_id, arg).await else { return };
+
     let perms = no_perms();
     match bot.set_chat_permissions(ChatId::from(chat_id), perms, None).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::ReadOnly).duration(secs)).await;
+            let until = match secs {
+                Some(s) => {
+                    sched.schedule(chat_id, s, Job::ReadOnlyEnd);
+                    format!("Lifts automatically in <b>{}</b>, or use <code>/unro</code>.", duration::fmt(s))
+                }
+                None => {
+                    sched.cancel(chat_id, &Job::ReadOnlyEnd);
+                    "Use <code>/unro</code> to restore.".to_string()
+                }
+            };
             mod_msg(bot, chat_id,
-                "🔇 <b>Read-only mode ON</b>\n\nOnly admins can send messages.\nUse <code>/unro</code> to restore.",
+                &format!("🔇 <b>Read-only mode ON</b>\n\nOnly admins can send messages.\n{}", until),
                 "main_menu").await;
         }
         Err(e) => {
@@ -518,10 +706,12 @@ pub async fn handle_ro(bot: &Bot, chat_id: i64) {
     }
 }
 
-pub async fn handle_unro(bot: &Bot, chat_id: i64) {
-    let perms = all_perms();
+pub async fn handle_unro(bot: &Bot, chat_id: i64, by: i64, db: &Db, sched: &Scheduler) {
+    let perms = locks::member_perms(db, chat_id);
     match bot.set_chat_permissions(ChatId::from(chat_id), perms, None).await {
         Ok(_) => {
+            audit::record(bot, db, Entry::new(chat_id, by, Action::Unro)).await;
+            sched.cancel(chat_id, &Job::ReadOnlyEnd);
             mod_msg(bot, chat_id,
                 "🔊 <b>Read-only mode OFF</b>\n\nAll members can send messages again.",
                 "main_menu").await;
@@ -534,44 +724,39 @@ pub async fn handle_unro(bot: &Bot, chat_id: i64) {
     }
 }
 
-// ── HTML escape ───────────────────────────────────────────────────────────────
+// ════════════════════════════════════════════════════════════════
+//  /pending — Scheduled actions for this chat
+// ════════════════════════════════════════════════════════════════
 
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+pub async fn handle_pending(bot: &Bot, chat_id: i64, sched: &Scheduler) {
+    let tasks = sched.pending(chat_id);
+    if tasks.is_empty() {
+        mod_msg(bot, chat_id, "⏰ <b>Scheduled actions</b>\n\n<i>Nothing pending.</i>", "main_menu").await;
+        return;
+    }
+    let now = duration::now();
+    let mut text = String::from("⏰ <b>Scheduled actions</b>\n");
+    for t in &tasks {
+        text.push_str(&format!("\n{} · in <b>{}</b>", t.job.describe(), duration::age(t.at - now)));
+    }
+    mod_msg(bot, chat_id, &text, "main_menu").await;
 }
 
+// ── HTML escape ───────────────────────────────────────────────────────────────
+
 // ── Moderation help text (used by /modhelp) ──────────────────────────────────
 
 pub async fn handle_mod_help(bot: &Bot, chat_id: i64) {
-    let text = "🛡️ <b>Moderation Commands</b>\n\n\
-        All commands below work by <b>replying</b> to the target user's message.\n\
-        Bot must be admin with appropriate rights.\n\n\
-        <b>👤 User Actions</b>\n\
-        /ban — Ban forever\n\
-        /ban 7d — Ban for 7 days\n\
-        /ban 2h — Ban for 2 hours\n\
-        /ban 30m — Ban for 30 minutes\n\
-        /unban — Unban a user\n\
-        /kick — Remove (can rejoin)\n\n\
-        <b>🔇 Mute</b>\n\
-        /mute — Mute forever\n\
-        /mute 1h — Mute for 1 hour\n\
-        /mute 30m — Mute for 30 minutes\n\
-        /unmute — Restore permissions\n\n\
-        <b>⚠️ Warnings</b>\n\
-        /warn — Warn user (auto-ban at 3)\n\
-        /unwarn — Remove one warning\n\
-        /warns — Check user's warnings\n\n\
-        <b>💬 Messages</b>\n\
-        /delete — Delete replied message\n\
-        /pin — Pin replied message\n\
-        /unpin — Unpin current message\n\n\
-        <b>🌐 Chat</b>\n\
-        /ro — Read-only mode (mutes everyone)\n\
-        /unro — Restore normal chat\n\n\
+    let text = format!(
+        "🛡️ <b>Moderation Commands</b>\n\n\
+        Target a user by replying to them, or by ID, @username or mention.\n\
+        Both you and the bot need the matching admin rights.\n\n\
+        {}\n\n\
         <b>⏱ Duration format</b>\n\
-        <code>Nd</code> = days, <code>Nh</code> = hours, <code>Nm</code> = minutes\n\
-        <i>Example: /ban 7d, /mute 2h, /ban 30m</i>";
+        <code>s</code> <code>m</code> <code>h</code> <code>d</code> <code>w</code>, combined like <code>1d12h</code>, or ISO-8601 like <code>PT30M</code>\n\
+        <i>Bans and mutes take 30s to 366d — omit for permanent</i>",
+        registry::help_sections(&[Category::Moderation, Category::Admin]),
+    );
 
     let kb = InlineKeyboardMarkup {
         inline_keyboard: vec![vec![btn("⬅️ Main Menu", "main_menu")]],
@@ -6,16 +6,9 @@
 //  /delnote <name>          delete a note
 // ════════════════════════════════════════════════════════════════
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 use tgbotrs::{gen_methods::SendMessageParams, types::InlineKeyboardMarkup, Bot, ReplyMarkup};
 use super::commands::btn;
-
-pub type NoteStore = Arc<Mutex<HashMap<(i64, String), String>>>;
-
-pub fn new_note_store() -> NoteStore {
-    Arc::new(Mutex::new(HashMap::new()))
-}
+use crate::storage::{chat_key, chat_prefix, Db, NOTES};
 
 fn he(s: &str) -> String { s.replace('&',"&amp;").replace('<',"&lt;").replace('>',"&gt;") }
 
@@ -26,7 +19,7 @@ async fn reply(bot: &Bot, chat_id: i64, text: &str) {
 }
 
 // /note name content
-pub async fn handle_save_note(bot: &Bot, chat_id: i64, args: &[&str], store: &NoteStore) {
+pub async fn handle_save_note(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
     if args.len() < 2 {
         reply(bot, chat_id,
             "⚠️ <b>Usage:</b> <code>/note name content</code>\n\n\
@@ -38,7 +31,7 @@ pub async fn handle_save_note(bot: &Bot, chat_id: i64, args: &[&str], store: &No
     }
     let name    = args[0].to_lowercase();
     let content = args[1..].join(" ");
-    { store.lock().unwrap().insert((chat_id, name.clone()), content.clone()); }
+    db.save(NOTES, &chat_key(chat_id, &name), &content);
     reply(bot, chat_id, &format!(
         "📝 <b>Note saved!</b>\n\n\
         📌 Name: <code>{name}</code>\n\
@@ -49,13 +42,13 @@ pub async fn handle_save_note(bot: &Bot, chat_id: i64, args: &[&str], store: &No
 }
 
 // /get name  or  #name trigger
-pub async fn handle_get_note(bot: &Bot, chat_id: i64, arg: Option<&str>, store: &NoteStore) {
+pub async fn handle_get_note(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
     let Some(raw) = arg else {
         reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/get note_name</code>").await;
         return;
     };
     let name = raw.to_lowercase().trim_start_matches('#').to_string();
-    let content = { store.lock().unwrap().get(&(chat_id, name.clone())).cloned() };
+    let content: Option<String> = db.load(NOTES, &chat_key(chat_id, &name));
     match content {
         Some(c) => { let _ = bot.send_message(chat_id, c, Some(SendMessageParams::new().parse_mode("HTML"))).await; }
         None => reply(bot, chat_id, &format!(
@@ -65,11 +58,12 @@ pub async fn handle_get_note(bot: &Bot, chat_id: i64, arg: Option<&str>, store:
 }
 
 // /notes
-pub async fn handle_list_notes(bot: &Bot, chat_id: i64, store: &NoteStore) {
-    let mut names: Vec<String> = {
-        let s = store.lock().unwrap();
-        s.keys().filter(|(cid,_)| *cid == chat_id).map(|(_,n)| n.clone()).collect()
-    };
+pub async fn handle_list_notes(bot: &Bot, chat_id: i64, db: &Db) {
+    let prefix = chat_prefix(chat_id);
+    let mut names: Vec<String> = db.scan(NOTES, &prefix)
+        .into_iter()
+        .map(|(k, _)| k[prefix.len()..].to_string())
+        .collect();
     if names.is_empty() {
         reply(bot, chat_id, "📂 <b>No notes saved.</b>\nUse <code>/note name content</code> to save one.").await;
         return;
@@ -83,13 +77,13 @@ pub async fn handle_list_notes(bot: &Bot, chat_id: i64, store: &NoteStore) {
 }
 
 // /delnote name
-pub async fn handle_del_note(bot: &Bot, chat_id: i64, arg: Option<&str>, store: &NoteStore) {
+pub async fn handle_del_note(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
     let Some(n) = arg else {
         reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/delnote name</code>").await;
         return;
     };
     let name = n.to_lowercase();
-    let removed = { store.lock().unwrap().remove(&(chat_id, name.clone())).is_some() };
+    let removed = db.delete(NOTES, &chat_key(chat_id, &name));
     if removed {
         reply(bot, chat_id, &format!("🗑️ <b>Note deleted:</b> <code>{}</code>", he(&name))).await;
     } else {
@@ -98,12 +92,12 @@ pub async fn handle_del_note(bot: &Bot, chat_id: i64, arg: Option<&str>, store:
 }
 
 // #hashtag auto-trigger — returns true if handled
-pub async fn check_hashtag_note(bot: &Bot, chat_id: i64, text: &str, store: &NoteStore) -> bool {
+pub async fn check_hashtag_note(bot: &Bot, chat_id: i64, text: &str, db: &Db) -> bool {
     if !text.starts_with('#') { return false; }
     let name = text.split_whitespace().next().unwrap_or("")
         .trim_start_matches('#').to_lowercase();
     if name.is_empty() { return false; }
-    let content = { store.lock().unwrap().get(&(chat_id, name)).cloned() };
+    let content: Option<String> = db.load(NOTES, &chat_key(chat_id, &name));
     if let Some(c) = content {
         let _ = bot.send_message(chat_id, c, Some(SendMessageParams::new().parse_mode("HTML"))).await;
         true
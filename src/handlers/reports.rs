@@ -0,0 +1,386 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Reports
+//  Members flag a message by replying with /report [reason] or
+//  @admin. A report card goes to the log channel and to admins who
+//  opted in with /reportpm; with neither, it is posted in the group
+//  and pings the admins. Card buttons: Delete, Warn, Mute 1h, Ban,
+//  Dismiss. Reports of the same message are merged into one card.
+//  /report [reason]   @admin   /reportpm [on|off]
+// ════════════════════════════════════════════════════════════════
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::{AnswerCallbackQueryParams, EditMessageTextParams, SendMessageParams},
+    types::{Chat, InlineKeyboardButton, InlineKeyboardMarkup, Message},
+    Bot, ChatId, ReplyMarkup,
+};
+
+use super::antiraid::ping_admins;
+use super::audit::{self, Action, Entry};
+use super::warns;
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::flood::FloodTracker;
+use crate::html::{self, reply};
+use crate::permissions::Right;
+use crate::scheduler::Scheduler;
+use crate::settings::{self, WarnAction};
+use crate::storage::{chat_key, chat_prefix, Db, CHATS, REPORTS, REPORT_PMS};
+use crate::targets::Target;
+
+/// Reports one member may file per chat within `REPORT_WINDOW`.
+const REPORT_LIMIT: usize = 3;
+const REPORT_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// Open reports older than this are forgotten.
+const REPORT_TTL: i64 = 7 * duration::DAY;
+const EXCERPT_LEN: usize = 300;
+const MUTE_SECS: i64 = duration::HOUR;
+
+/// A reported message, keyed by `chat_key(chat, message_id)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Report {
+    user_id: i64,
+    name: String,
+    /// (id, first name) of everyone who reported it, first reporter first.
+    reporters: Vec<(i64, String)>,
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    excerpt: String,
+    #[serde(default)]
+    link: Option<String>,
+    at: i64,
+    /// Button verbs already used, so each runs once.
+    #[serde(default)]
+    done: Vec<String>,
+    /// What admins did so far, one HTML line each.
+    #[serde(default)]
+    history: Vec<String>,
+    /// (chat, message) of every card.
+    #[serde(default)]
+    cards: Vec<(i64, i64)>,
+}
+
+fn chat_title(db: &Db, chat_id: i64) -> String {
+    db.get(CHATS, &chat_id.to_string())
+        .and_then(|v| v.get("title").and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_else(|| chat_id.to_string())
+}
+
+fn user_link(id: i64, name: &str) -> String {
+    format!("<a href=\"tg://user?id={}\">{}</a>", id, html::escape(name))
+}
+
+/// t.me link to a message; only public chats and supergroups have one.
+fn message_link(chat: &Chat, message_id: i64) -> Option<String> {
+    match &chat.username {
+        Some(u) => Some(format!("https://t.me/{}/{}", u, message_id)),
+        None if chat.id < -1_000_000_000_000 => Some(format!("https://t.me/c/{}/{}", -chat.id - 1_000_000_000_000, message_id)),
+        None => None,
+    }
+}
+
+fn excerpt(msg: &Message) -> String {
+    let text = msg.text.as_deref().or(msg.caption.as_deref()).unwrap_or("");
+    let mut out: String = text.chars().take(EXCERPT_LEN).collect();
+    if text.chars().count() > EXCERPT_LEN { out.push('…'); }
+    if out.is_empty() { out = "[media]".to_string(); }
+    out
+}
+
+/// If a plain message calls the admins ("spam @admin"), the rest of it,
+/// which becomes the report reason.
+pub fn admin_call(text: &str) -> Option<String> {
+    let is_call = |w: &str| {
+        let w = w.trim_end_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        w == "@admin" || w == "@admins"
+    };
+    if !text.split_whitespace().any(is_call) { return None; }
+    Some(text.split_whitespace().filter(|w| !is_call(w)).collect::<Vec<_>>().join(" "))
+}
+
+// ── Report card ──────────────────────────────────────────────────────────────
+//   Button data is `report:<d|w|m|b|x>:<chat_id>:<message_id>`, so cards
+//   work from a log channel or a private chat too.
+
+fn card_text(db: &Db, chat_id: i64, r: &Report) -> String {
+    let (first_id, first_name) = &r.reporters[0];
+    let mut by = user_link(*first_id, first_name);
+    if r.reporters.len() > 1 { by.push_str(&format!(" and {} more", r.reporters.len() - 1)); }
+    let mut text = format!(
+        "🚩 <b>Report</b> · {}\n\n\
+        👤 <b>Reported:</b> {} (<code>{}</code>)\n\
+        🙋 <b>By:</b> {}",
+        html::escape(&chat_title(db, chat_id)), user_link(r.user_id, &r.name), r.user_id, by);
+    if !r.reason.is_empty() { text.push_str(&format!("\n📝 <b>Reason:</b> {}", html::escape(&r.reason))); }
+    text.push_str(&format!("\n💬 <i>{}</i>", html::escape(&r.excerpt)));
+    if let Some(l) = &r.link { text.push_str(&format!("\n🔗 <a href=\"{}\">Go to message</a>", l)); }
+    text.push_str(&format!("\n🕐 {} ago", duration::age(duration::now() - r.at)));
+    if !r.history.is_empty() { text.push_str(&format!("\n\n{}", r.history.join("\n"))); }
+    text
+}
+
+fn card_keyboard(chat_id: i64, message_id: i64, done: &[String]) -> InlineKeyboardMarkup {
+    let b = |text: &str, verb: &str| InlineKeyboardButton {
+        text: text.to_string(),
+        callback_data: Some(format!("report:{}:{}:{}", verb, chat_id, message_id)),
+        ..Default::default()
+    };
+    let open = |verb: &str| !done.iter().any(|d| d == verb);
+    let mut first = Vec::new();
+    if open("d") { first.push(b("🗑 Delete", "d")); }
+    if open("w") { first.push(b("⚠️ Warn", "w")); }
+    if open("m") { first.push(b("🔇 Mute 1h", "m")); }
+    let mut rows = vec![first, vec![b("🔨 Ban", "b"), b("✖️ Dismiss", "x")]];
+    rows.retain(|r| !r.is_empty());
+    InlineKeyboardMarkup { inline_keyboard: rows }
+}
+
+async fn edit_cards(bot: &Bot, cards: &[(i64, i64)], text: &str, kb: Option<InlineKeyboardMarkup>) {
+    for card in cards {
+        let mut p = EditMessageTextParams::new()
+            .chat_id(ChatId::from(card.0))
+            .message_id(card.1)
+            .parse_mode("HTML");
+        if let Some(kb) = kb.clone() { p = p.reply_markup(kb); }
+        let _ = bot.edit_message_text(text, Some(p)).await;
+    }
+}
+
+/// Admins of `chat_id` who asked for reports in private and still are admins.
+async fn pm_admins(bot: &Bot, chat_id: i64, db: &Db, admins: &AdminCache) -> Vec<i64> {
+    let prefix = chat_prefix(chat_id);
+    let mut out = Vec::new();
+    for (key, _) in db.scan(REPORT_PMS, &prefix) {
+        let Ok(id) = key[prefix.len()..].parse::<i64>() else { continue };
+        if admins.lookup(bot, chat_id, id).await.is_ok_and(|m| m.is_admin()) {
+            out.push(id);
+        }
+    }
+    out
+}
+
+fn prune(db: &Db, chat_id: i64) {
+    let cutoff = duration::now() - REPORT_TTL;
+    for (key, r) in db.load_all::<Report>(REPORTS, &chat_prefix(chat_id)) {
+        if r.at < cutoff { db.delete(REPORTS, &key); }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /report [reason] and @admin — on a replied-to message
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_report(bot: &Bot, msg: &Message, reason: &str, db: &Db, flood: &FloodTracker, admins: &AdminCache) {
+    let chat_id = msg.chat.id;
+    let Some(reporter) = &msg.from else { return };
+    let Some(reported) = msg.reply_to_message.as_deref() else {
+        reply(bot, chat_id, "⚠️ <b>Usage:</b> reply to the message you want to report with <code>/report [reason]</code> or <code>@admin</code>.").await;
+        return;
+    };
+    // Channel posts and anonymous admins have no user to act on.
+    let Some(user) = reported.from.as_deref().filter(|_| reported.sender_chat.is_none()) else {
+        reply(bot, chat_id, "ℹ️ Posts sent on behalf of a chat can't be reported.").await;
+        return;
+    };
+    if user.id == reporter.id || user.id == bot.me.id { return; }
+    if admins.lookup(bot, chat_id, user.id).await.is_ok_and(|m| m.is_admin()) {
+        reply(bot, chat_id, "🛡 Admins can't be reported.").await;
+        return;
+    }
+    let reporter_is_admin = admins.lookup(bot, chat_id, reporter.id).await.is_ok_and(|m| m.is_admin());
+    if !reporter_is_admin && !flood.report(chat_id, reporter.id, REPORT_LIMIT, REPORT_WINDOW) {
+        reply(bot, chat_id, "⏳ You've sent several reports just now — please wait a few minutes.").await;
+        return;
+    }
+
+    let key = chat_key(chat_id, reported.message_id);
+    let reason: String = reason.chars().take(EXCERPT_LEN).collect();
+    let mut new = false;
+    let r = db.modify::<Report, _>(REPORTS, &key, |old| {
+        let mut r = old.unwrap_or_else(|| {
+            new = true;
+            Report {
+                user_id: user.id,
+                name: user.first_name.clone(),
+                reporters: Vec::new(),
+                reason: String::new(),
+                excerpt: excerpt(reported),
+                link: message_link(&msg.chat, reported.message_id),
+                at: duration::now(),
+                done: Vec::new(),
+                history: Vec::new(),
+                cards: Vec::new(),
+            }
+        });
+        if !r.reporters.iter().any(|(id, _)| *id == reporter.id) {
+            r.reporters.push((reporter.id, reporter.first_name.clone()));
+        }
+        if r.reason.is_empty() { r.reason = reason.clone(); }
+        Some(r)
+    });
+    let Some(mut r) = r else { return };
+    let text = card_text(db, chat_id, &r);
+    let kb = card_keyboard(chat_id, reported.message_id, &r.done);
+
+    // A repeat report only updates the cards already out there.
+    if !new {
+        edit_cards(bot, &r.cards, &text, Some(kb)).await;
+        reply(bot, chat_id, "✅ Reported to the admins.").await;
+        return;
+    }
+    prune(db, chat_id);
+
+    let mut dests: Vec<i64> = settings::load(db, chat_id).log_channel.into_iter().collect();
+    dests.extend(pm_admins(bot, chat_id, db, admins).await);
+    for dest in dests {
+        let p = SendMessageParams::new()
+            .parse_mode("HTML")
+            .reply_markup(ReplyMarkup::InlineKeyboard(kb.clone()));
+        match bot.send_message(dest, &text, Some(p)).await {
+            Ok(sent) => r.cards.push((dest, sent.message_id)),
+            Err(e)   => log::warn!("reports: could not deliver a report from {} to {}: {}", chat_id, dest, e),
+        }
+    }
+    if r.cards.is_empty() {
+        // Nobody to send it to privately, so the group sees it.
+        let p = SendMessageParams::new()
+            .parse_mode("HTML")
+            .reply_markup(ReplyMarkup::InlineKeyboard(kb));
+        let text = format!("{}{}", text, ping_admins(bot, chat_id, admins).await);
+        match bot.send_message(chat_id, text, Some(p)).await {
+            Ok(sent) => r.cards.push((chat_id, sent.message_id)),
+            Err(e)   => log::warn!("reports: could not post a report in {}: {}", chat_id, e),
+        }
+    } else {
+        reply(bot, chat_id, "✅ Reported to the admins.").await;
+    }
+    db.save(REPORTS, &key, &r);
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Card buttons — `report:<verb>:<chat_id>:<message_id>`
+//  Delete needs the delete right, Warn/Mute/Ban the ban right, and
+//  any admin may dismiss. Ban and Dismiss close the report.
+// ════════════════════════════════════════════════════════════════
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_action(bot: &Bot, cq_id: &str, presser: i64, presser_name: &str, data: &str, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    let toast = |text: &'static str| {
+        let p = AnswerCallbackQueryParams::new().text(text);
+        bot.answer_callback_query(cq_id, Some(p))
+    };
+    let mut parts = data.splitn(3, ':');
+    let (Some(verb), Some(Ok(chat_id)), Some(Ok(message_id))) = (
+        parts.next(),
+        parts.next().map(str::parse::<i64>),
+        parts.next().map(str::parse::<i64>),
+    ) else { return };
+    let right = match verb {
+        "d"             => Some(Right::Delete),
+        "w" | "m" | "b" => Some(Right::Restrict),
+        "x"             => None,
+        _ => return,
+    };
+
+    match admins.lookup(bot, chat_id, presser).await {
+        Ok(m) if right.map_or(m.is_admin(), |r| m.has(r)) => {}
+        Ok(m) if m.is_admin() => { let _ = toast("🚫 You don't have the admin right for that.").await; return; }
+        _ => { let _ = toast("🚫 Only admins of that group can handle reports.").await; return; }
+    }
+
+    let key = chat_key(chat_id, message_id);
+    let Some(mut r) = db.load::<Report>(REPORTS, &key) else {
+        let _ = toast("ℹ️ This report was already closed.").await;
+        return;
+    };
+    if r.done.iter().any(|d| d == verb) {
+        let _ = toast("ℹ️ Already done.").await;
+        return;
+    }
+
+    let t = Target { id: r.user_id, name: r.name.clone() };
+    let reason = if r.reason.is_empty() { "reported message".to_string() } else { format!("reported: {}", r.reason) };
+    let chat = ChatId::from(chat_id);
+    let result: Result<String, String> = match verb {
+        "d" => match bot.delete_message(chat, message_id).await {
+            Ok(_) => {
+                audit::record(bot, db, Entry::new(chat_id, presser, Action::Delete).target(&t).reason(&reason)).await;
+                Ok("🗑 Message deleted".into())
+            }
+            Err(e) => Err(format!("Could not delete: <code>{}</code>", e)),
+        },
+        "w" => {
+            warns::warn_user(bot, chat_id, presser, &t, &reason, db, sched).await;
+            Ok("⚠️ Warned".into())
+        }
+        "m" | "b" => {
+            let action = if verb == "m" { WarnAction::Mute { secs: Some(MUTE_SECS) } } else { WarnAction::Ban };
+            match warns::punish(bot, chat_id, presser, &t, action, &reason, db, sched).await {
+                Ok(_) if verb == "m" => Ok(format!("🔇 Muted for {}", duration::fmt(MUTE_SECS))),
+                Ok(_) => Ok("🔨 Banned".into()),
+                Err(e) => Err(format!("Could not {}: <code>{}</code>", warns::action_label(action), e)),
+            }
+        }
+        _ => Ok("✖️ Dismissed".into()),
+    };
+
+    let ok = result.is_ok();
+    match result {
+        Ok(line) => {
+            r.history.push(format!("{} by {}", line, html::escape(presser_name)));
+            r.done.push(verb.to_string());
+        }
+        Err(line) => r.history.push(format!("⚠️ {}", line)),
+    }
+    let text = card_text(db, chat_id, &r);
+    if ok && (verb == "b" || verb == "x") {
+        db.delete(REPORTS, &key);
+        edit_cards(bot, &r.cards, &text, None).await;
+    } else {
+        db.save(REPORTS, &key, &r);
+        edit_cards(bot, &r.cards, &text, Some(card_keyboard(chat_id, message_id, &r.done))).await;
+    }
+    let _ = toast(if ok { "Done." } else { "Failed — see the card." }).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /reportpm [on|off] — an admin's own choice of private reports
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_report_pm(bot: &Bot, chat_id: i64, user_id: i64, arg: Option<&str>, db: &Db, admins: &AdminCache) {
+    if !admins.lookup(bot, chat_id, user_id).await.is_ok_and(|m| m.is_admin()) {
+        reply(bot, chat_id, "🚫 Only chat admins can receive reports.").await;
+        return;
+    }
+    let key = chat_key(chat_id, user_id);
+    match arg.map(str::to_lowercase).as_deref() {
+        Some("on") => {
+            let hello = format!("🚩 You'll get reports from <b>{}</b> here.", html::escape(&chat_title(db, chat_id)));
+            let p = SendMessageParams::new().parse_mode("HTML");
+            if bot.send_message(user_id, hello, Some(p)).await.is_err() {
+                reply(bot, chat_id, &format!(
+                    "❌ I can't message you yet. Open a private chat with @{} and press Start, then try again.",
+                    bot.me.username.as_deref().unwrap_or("me"))).await;
+                return;
+            }
+            db.save(REPORT_PMS, &key, &true);
+            reply(bot, chat_id, "✅ You'll receive reports from this chat in private.").await;
+        }
+        Some("off") => {
+            db.delete(REPORT_PMS, &key);
+            reply(bot, chat_id, "✅ You'll no longer receive reports in private.").await;
+        }
+        Some(_) => reply(bot, chat_id, "⚠️ <b>Usage:</b> <code>/reportpm on|off</code>").await,
+        None => {
+            let state = if db.get(REPORT_PMS, &key).is_some() { "on" } else { "off" };
+            let log = if settings::load(db, chat_id).log_channel.is_some() { "also go to the log channel" } else { "have no log channel" };
+            reply(bot, chat_id, &format!(
+                "🚩 Private reports for you: <b>{}</b>. Reports {}.\n\n\
+                <i>Change with</i> <code>/reportpm on</code> <i>or</i> <code>/reportpm off</code>",
+                state, log)).await;
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — System Handlers (owner / sudo only)
+//  /setcommands  /deletecommands  /deletewebhook
+//  /addsudo  /rmsudo  /sudolist
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::{gen_methods::SendMessageParams, types::InlineKeyboardMarkup, Bot, ReplyMarkup};
+
+use super::commands::{btn, delete_commands, register_commands};
+use crate::roles::Roles;
+use crate::storage::Db;
+use crate::targets::{self, Resolved};
+
+async fn reply(bot: &Bot, chat_id: i64, text: &str) {
+    let kb = InlineKeyboardMarkup { inline_keyboard: vec![vec![btn("⬅️ Menu","main_menu")]] };
+    let p = SendMessageParams::new().parse_mode("HTML").reply_markup(ReplyMarkup::InlineKeyboard(kb));
+    let _ = bot.send_message(chat_id, text, Some(p)).await;
+}
+
+// ── /setcommands /deletecommands /deletewebhook ───────────────────────────────
+
+pub async fn handle_set_commands(bot: &Bot, chat_id: i64, roles: &Roles) {
+    match register_commands(bot, roles.owner()).await {
+        Ok(_)  => reply(bot, chat_id, "✅ Commands registered!").await,
+        Err(e) => reply(bot, chat_id, &format!("❌ Error: {}", e)).await,
+    }
+}
+
+pub async fn handle_delete_commands(bot: &Bot, chat_id: i64, roles: &Roles, db: &Db) {
+    match delete_commands(bot, roles.owner(), db).await {
+        Ok(_)  => reply(bot, chat_id, "✅ Commands deleted!").await,
+        Err(e) => reply(bot, chat_id, &format!("❌ Error: {}", e)).await,
+    }
+}
+
+pub async fn handle_delete_webhook(bot: &Bot, chat_id: i64) {
+    match bot.delete_webhook(None).await {
+        Ok(_)  => reply(bot, chat_id, "✅ Webhook deleted!").await,
+        Err(e) => reply(bot, chat_id, &format!("❌ Error: {}", e)).await,
+    }
+}
+
+// ── /addsudo <user> ───────────────────────────────────────────────────────────
+
+pub async fn handle_add_sudo(bot: &Bot, chat_id: i64, target: &Resolved, roles: &Roles) {
+    let Some(uid) = targets::require(bot, chat_id, target, "/addsudo", "").await.map(|t| t.id) else { return };
+    if roles.add_sudo(uid) {
+        log::info!("sudo granted to {}", uid);
+        reply(bot, chat_id, &format!(
+            "🛡️ <a href=\"tg://user?id={uid}\">{uid}</a> is now a <b>sudo</b> user.", uid=uid)).await;
+    } else {
+        reply(bot, chat_id, &format!("ℹ️ <code>{}</code> already has sudo or owner rights.", uid)).await;
+    }
+}
+
+// ── /rmsudo <user> ────────────────────────────────────────────────────────────
+
+pub async fn handle_rm_sudo(bot: &Bot, chat_id: i64, target: &Resolved, roles: &Roles) {
+    let Some(uid) = targets::require(bot, chat_id, target, "/rmsudo", "").await.map(|t| t.id) else { return };
+    if roles.remove_sudo(uid) {
+        log::info!("sudo revoked from {}", uid);
+        reply(bot, chat_id, &format!("🔽 <code>{}</code> is no longer sudo.", uid)).await;
+    } else {
+        reply(bot, chat_id, &format!("❓ <code>{}</code> is not on the sudo list.", uid)).await;
+    }
+}
+
+// ── /sudolist ─────────────────────────────────────────────────────────────────
+
+pub async fn handle_sudo_list(bot: &Bot, chat_id: i64, roles: &Roles) {
+    let owner = roles.owner()
+        .map(|id| format!("👑 <a href=\"tg://user?id={id}\">{id}</a>", id=id))
+        .unwrap_or_else(|| "👑 <i>not set</i>".into());
+    let ids = roles.sudo_list();
+    let list: String = if ids.is_empty() {
+        "<i>No sudo users.</i>".into()
+    } else {
+        ids.iter().map(|id| format!("🛡️ <code>{}</code>\n", id)).collect()
+    };
+    reply(bot, chat_id, &format!(
+        "🔐 <b>Bot Roles</b>\n\n<b>Owner</b>\n{}\n\n<b>Sudo</b> ({})\n{}",
+        owner, ids.len(), list)).await;
+}
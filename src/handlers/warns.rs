@@ -0,0 +1,332 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Warnings
+//  /warn  /unwarn  /warns  /resetwarns
+//  /warnlimit  /warnmode  /warntime   (per-chat policy)
+//  Each warning keeps its reason, moderator and time. When a user
+//  reaches the chat's limit the configured action runs and their
+//  record is cleared.
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::{BanChatMemberParams, RestrictChatMemberParams, SendMessageParams},
+    types::InlineKeyboardMarkup,
+    Bot, ChatId, ReplyMarkup,
+};
+
+use super::audit::{self, Action, Entry};
+use super::commands::btn;
+use super::moderation::no_perms;
+use crate::admin_cache::AdminCache;
+use crate::duration;
+use crate::html;
+use crate::permissions;
+use crate::scheduler::{Job, Scheduler};
+use crate::settings::{self, WarnAction, WarnPolicy};
+use crate::storage::{chat_key, Db, WARNS};
+use crate::targets::{self, Resolved, Target};
+
+const MAX_WARN_LIMIT: u8 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warning {
+    pub reason: String,
+    /// Moderator user id (0 for warnings migrated from the old counter).
+    pub by: i64,
+    pub at: i64,
+}
+
+async fn reply(bot: &Bot, chat_id: i64, text: &str) {
+    let kb = InlineKeyboardMarkup { inline_keyboard: vec![vec![btn("⬅️ Menu", "main_menu")]] };
+    let p = SendMessageParams::new().parse_mode("HTML").reply_markup(ReplyMarkup::InlineKeyboard(kb));
+    let _ = bot.send_message(chat_id, text, Some(p)).await;
+}
+
+/// Drops warnings older than the policy's expiry.
+fn active(list: Vec<Warning>, policy: &WarnPolicy, now: i64) -> Vec<Warning> {
+    match policy.expiry {
+        Some(secs) => list.into_iter().filter(|w| w.at + secs > now).collect(),
+        None => list,
+    }
+}
+
+fn load_active(db: &Db, chat_id: i64, user_id: i64, policy: &WarnPolicy) -> Vec<Warning> {
+    let list: Vec<Warning> = db.load(WARNS, &chat_key(chat_id, user_id)).unwrap_or_default();
+    active(list, policy, duration::now())
+}
+
+fn bars(count: usize, limit: u8) -> String {
+    "⚠️".repeat(count) + &"▪️".repeat((limit as usize).saturating_sub(count))
+}
+
+pub fn action_label(action: WarnAction) -> String {
+    match action {
+        WarnAction::Ban                    => "ban".into(),
+        WarnAction::Tban { secs }          => format!("ban for {}", duration::fmt(secs)),
+        WarnAction::Kick                   => "kick".into(),
+        WarnAction::Mute { secs: None }    => "mute".into(),
+        WarnAction::Mute { secs: Some(s) } => format!("mute for {}", duration::fmt(s)),
+    }
+}
+
+/// Audit-log action and duration for a warn-limit action.
+fn audit_action(action: WarnAction) -> (Action, Option<i64>) {
+    match action {
+        WarnAction::Ban           => (Action::Ban, None),
+        WarnAction::Tban { secs } => (Action::Ban, Some(secs)),
+        WarnAction::Kick          => (Action::Kick, None),
+        WarnAction::Mute { secs } => (Action::Mute, secs),
+    }
+}
+
+async fn enforce(bot: &Bot, chat_id: i64, user_id: i64, action: WarnAction) -> Result<(), tgbotrs::BotError> {
+    let chat = ChatId::from(chat_id);
+    match action {
+        WarnAction::Ban => {
+            bot.ban_chat_member(chat, user_id, Some(BanChatMemberParams::new().revoke_messages(true))).await?;
+        }
+        WarnAction::Tban { secs } => {
+            let p = BanChatMemberParams::new().until_date(duration::now() + secs);
+            bot.ban_chat_member(chat, user_id, Some(p)).await?;
+        }
+        WarnAction::Kick => {
+            bot.ban_chat_member(chat.clone(), user_id, None).await?;
+            let _ = bot.unban_chat_member(chat, user_id, None).await;
+        }
+        WarnAction::Mute { secs } => {
+            let mut p = RestrictChatMemberParams::new();
+            if let Some(s) = secs { p = p.until_date(duration::now() + s); }
+            bot.restrict_chat_member(chat, user_id, no_perms(), Some(p)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// `enforce`, plus the audit entry and, for timed actions, the expiry job.
+#[allow(clippy::too_many_arguments)]
+pub async fn punish(bot: &Bot, chat_id: i64, by: i64, t: &Target, action: WarnAction, reason: &str, db: &Db, sched: &Scheduler) -> Result<(), tgbotrs::BotError> {
+    enforce(bot, chat_id, t.id, action).await?;
+    let (logged, secs) = audit_action(action);
+    audit::record(bot, db, Entry::new(chat_id, by, logged).target(t).duration(secs).reason(reason)).await;
+    if let Some(s) = secs {
+        let (user_id, name) = (t.id, t.name.clone());
+        let job = if logged == Action::Ban { Job::BanExpired { user_id, name } }
+                  else { Job::MuteExpired { user_id, name } };
+        sched.schedule(chat_id, s, job);
+    }
+    Ok(())
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /warn <user> [reason]
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_warn(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db, sched: &Scheduler, admins: &AdminCache) {
+    let Some(t) = targets::require(bot, chat_id, target, "/warn", "[reason]").await else { return };
+    if !permissions::guard_target(bot, admins, chat_id, t.id, false).await { return; }
+    warn_user(bot, chat_id, by, t, &target.rest, db, sched).await;
+}
+
+/// Adds a warning and, at the limit, runs the chat's warn action. Also
+/// used by automatic enforcement (blocklist), with the bot as `by`.
+pub async fn warn_user(bot: &Bot, chat_id: i64, by: i64, t: &Target, reason: &str, db: &Db, sched: &Scheduler) {
+    let policy = settings::load(db, chat_id).warn;
+    let now = duration::now();
+    let key = chat_key(chat_id, t.id);
+    let warning = Warning { reason: reason.to_string(), by, at: now };
+    let list = db.modify::<Vec<Warning>, _>(WARNS, &key, |old| {
+        let mut list = active(old.unwrap_or_default(), &policy, now);
+        list.push(warning);
+        Some(list)
+    }).unwrap_or_default();
+
+    audit::record(bot, db, Entry::new(chat_id, by, Action::Warn).target(t).reason(reason)).await;
+
+    let count = list.len();
+    let reason = if reason.is_empty() { String::new() }
+                 else { format!("\n<b>Reason:</b> {}", html::escape(reason)) };
+    let who = format!("<a href=\"tg://user?id={}\">{}</a>", t.id, html::escape(&t.name));
+
+    if count < policy.limit as usize {
+        reply(bot, chat_id, &format!(
+            "⚠️ <b>Warning {}/{}</b> issued to {}{}\n\n{}\n\n<i>{} warnings = {}.</i>",
+            count, policy.limit, who, reason, bars(count, policy.limit),
+            policy.limit, action_label(policy.action))).await;
+        return;
+    }
+
+    // Warnings are only spent once the action has actually been taken.
+    match punish(bot, chat_id, by, t, policy.action, &format!("{} warnings", count), db, sched).await {
+        Ok(_) => {
+            db.delete(WARNS, &key);
+            reply(bot, chat_id, &format!(
+                "🔨 {} reached <b>{}/{} warnings</b> — action: <b>{}</b>.{}",
+                who, count, policy.limit, action_label(policy.action), reason)).await
+        }
+        Err(e) => reply(bot, chat_id, &format!(
+            "❌ {} reached the warn limit, but <b>{}</b> failed: <code>{}</code>",
+            who, action_label(policy.action), e)).await,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /unwarn <user> — Remove the most recent warning
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_unwarn(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db) {
+    let Some(t) = targets::require(bot, chat_id, target, "/unwarn", "").await else { return };
+
+    let policy = settings::load(db, chat_id).warn;
+    let now = duration::now();
+    let mut had_any = false;
+    let list = db.modify::<Vec<Warning>, _>(WARNS, &chat_key(chat_id, t.id), |old| {
+        let mut list = active(old.unwrap_or_default(), &policy, now);
+        had_any = list.pop().is_some();
+        Some(list).filter(|l| !l.is_empty())
+    }).unwrap_or_default();
+    if !had_any {
+        reply(bot, chat_id, &format!(
+            "ℹ️ <a href=\"tg://user?id={}\">{}</a> has no warnings to remove.",
+            t.id, html::escape(&t.name))).await;
+        return;
+    }
+    audit::record(bot, db, Entry::new(chat_id, by, Action::Unwarn).target(t)).await;
+
+    reply(bot, chat_id, &format!(
+        "✅ Warning removed from <a href=\"tg://user?id={}\">{}</a>\n\nCurrent warnings: <b>{}/{}</b>",
+        t.id, html::escape(&t.name), list.len(), policy.limit)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /resetwarns <user> — Clear a user's warnings
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_reset_warns(bot: &Bot, chat_id: i64, by: i64, target: &Resolved, db: &Db) {
+    let Some(t) = targets::require(bot, chat_id, target, "/resetwarns", "").await else { return };
+
+    let text = if db.delete(WARNS, &chat_key(chat_id, t.id)) {
+        audit::record(bot, db, Entry::new(chat_id, by, Action::ResetWarns).target(t)).await;
+        format!("🧹 Warnings cleared for <a href=\"tg://user?id={}\">{}</a>.", t.id, html::escape(&t.name))
+    } else {
+        format!("ℹ️ <a href=\"tg://user?id={}\">{}</a> has no warnings.", t.id, html::escape(&t.name))
+    };
+    reply(bot, chat_id, &text).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  /warns <user> — List active warnings with reasons
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_warns(bot: &Bot, chat_id: i64, target: &Resolved, db: &Db) {
+    let Some(t) = targets::require(bot, chat_id, target, "/warns", "").await else { return };
+
+    let policy = settings::load(db, chat_id).warn;
+    let list = load_active(db, chat_id, t.id, &policy);
+    let now = duration::now();
+
+    let mut text = format!(
+        "📋 <b>Warnings for</b> <a href=\"tg://user?id={}\">{}</a>: <b>{}/{}</b>\n\n{}",
+        t.id, html::escape(&t.name), list.len(), policy.limit, bars(list.len(), policy.limit));
+    for (i, w) in list.iter().enumerate() {
+        let reason = if w.reason.is_empty() { "<i>no reason</i>".to_string() } else { html::escape(&w.reason) };
+        let by = match w.by {
+            0  => "unknown".to_string(),
+            id => format!("<a href=\"tg://user?id={}\">{}</a>", id, html::escape(
+                &targets::known(db, id).map(|k| k.first_name).unwrap_or_else(|| id.to_string()))),
+        };
+        text.push_str(&format!("\n\n{}. {}\n   <i>by</i> {} · {} ago", i + 1, reason, by, duration::age(now - w.at)));
+    }
+    if let Some(secs) = policy.expiry {
+        text.push_str(&format!("\n\n<i>Warnings expire after {}.</i>", duration::fmt(secs)));
+    }
+    reply(bot, chat_id, &text).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Policy: /warnlimit <n>  /warnmode <action>  /warntime <duration|off>
+//  Without an argument each shows the current value.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn handle_warn_limit(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let Some(a) = arg else {
+        let p = settings::load(db, chat_id).warn;
+        reply(bot, chat_id, &format!("⚙️ Warn limit: <b>{}</b>\n\n<i>Change with</i> <code>/warnlimit 5</code>", p.limit)).await;
+        return;
+    };
+    match a.parse::<u8>() {
+        Ok(n) if (1..=MAX_WARN_LIMIT).contains(&n) => {
+            settings::update(db, chat_id, |s| s.warn.limit = n);
+            reply(bot, chat_id, &format!("✅ Warn limit set to <b>{}</b>.", n)).await;
+        }
+        _ => reply(bot, chat_id, &format!("⚠️ Limit must be a number from 1 to {}.", MAX_WARN_LIMIT)).await,
+    }
+}
+
+/// `Err(None)` when the arguments don't match the usage; `Err(Some(why))`
+/// for a duration that doesn't parse or that Telegram wouldn't honour.
+pub fn parse_action(args: &[&str]) -> Result<WarnAction, Option<String>> {
+    let secs = |s: Option<&&str>| -> Result<i64, Option<String>> {
+        let s = s.ok_or(None)?;
+        duration::parse_restriction(s).map_err(|e| Some(format!("<code>{}</code>: {}", html::escape(s), e)))
+    };
+    match args.first().map(|a| a.to_lowercase()).as_deref() {
+        Some("ban")   => Ok(WarnAction::Ban),
+        Some("kick")  => Ok(WarnAction::Kick),
+        Some("tban")  => Ok(WarnAction::Tban { secs: secs(args.get(1))? }),
+        Some("mute")  => match args.get(1) {
+            Some(_) => Ok(WarnAction::Mute { secs: Some(secs(args.get(1))?) }),
+            None    => Ok(WarnAction::Mute { secs: None }),
+        },
+        Some("tmute") => Ok(WarnAction::Mute { secs: Some(secs(args.get(1))?) }),
+        _ => Err(None),
+    }
+}
+
+pub async fn handle_warn_mode(bot: &Bot, chat_id: i64, args: &[&str], db: &Db) {
+    if args.is_empty() {
+        let p = settings::load(db, chat_id).warn;
+        reply(bot, chat_id, &format!(
+            "⚙️ At the warn limit: <b>{}</b>\n\n\
+            <i>Options:</i> <code>ban</code> · <code>tban 1d</code> · <code>kick</code> · \
+            <code>mute</code> · <code>mute 1h</code>", action_label(p.action))).await;
+        return;
+    }
+    match parse_action(args) {
+        Ok(action) => {
+            settings::update(db, chat_id, |s| s.warn.action = action);
+            reply(bot, chat_id, &format!("✅ At the warn limit users now get: <b>{}</b>.", action_label(action))).await;
+        }
+        Err(None) => reply(bot, chat_id,
+            "⚠️ <b>Usage:</b> <code>/warnmode ban|tban &lt;duration&gt;|kick|mute [duration]</code>").await,
+        Err(Some(why)) => reply(bot, chat_id, &format!("⚠️ <b>Invalid duration</b> {}", why)).await,
+    }
+}
+
+pub async fn handle_warn_time(bot: &Bot, chat_id: i64, arg: Option<&str>, db: &Db) {
+    let Some(a) = arg else {
+        let text = match settings::load(db, chat_id).warn.expiry {
+            Some(secs) => format!("⚙️ Warnings expire after <b>{}</b>.", duration::fmt(secs)),
+            None       => "⚙️ Warnings never expire.".to_string(),
+        };
+        reply(bot, chat_id, &format!("{}\n\n<i>Change with</i> <code>/warntime 7d</code> <i>or</i> <code>/warntime off</code>", text)).await;
+        return;
+    };
+    let expiry = match a.to_lowercase().as_str() {
+        "off" | "never" | "0" => None,
+        other => match duration::parse(other) {
+            Ok(secs) => Some(secs),
+            Err(e) => {
+                reply(bot, chat_id, &format!(
+                    "⚠️ <code>{}</code>: {}\n\n<b>Usage:</b> <code>/warntime 7d</code> or <code>/warntime off</code>",
+                    html::escape(other), e)).await;
+                return;
+            }
+        },
+    };
+    settings::update(db, chat_id, |s| s.warn.expiry = expiry);
+    let text = match expiry {
+        Some(secs) => format!("✅ Warnings now expire after <b>{}</b>.", duration::fmt(secs)),
+        None       => "✅ Warnings no longer expire.".to_string(),
+    };
+    reply(bot, chat_id, &text).await;
+}
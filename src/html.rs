@@ -0,0 +1,18 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — HTML helpers
+//  Every reply is sent with parse_mode HTML, so user-supplied text
+//  (names, reasons, patterns) must be escaped before it is quoted.
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::{gen_methods::SendMessageParams, Bot};
+
+/// Escapes `&`, `<` and `>` for Telegram's HTML parse mode.
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Sends `text` as an HTML message; failures are ignored.
+pub async fn reply(bot: &Bot, chat_id: i64, text: &str) {
+    let p = SendMessageParams::new().parse_mode("HTML");
+    let _ = bot.send_message(chat_id, text, Some(p)).await;
+}
@@ -8,15 +8,28 @@
 //  Version:   0.1.0 | tgbotrs 0.1.4 | Telegram Bot API 9.4
 // ════════════════════════════════════════════════════════════════
 
+mod admin_cache;
 mod config;
 mod dispatcher;
+mod duration;
+mod flood;
 mod handlers;
-
+mod html;
+mod permissions;
+mod registry;
+mod roles;
+mod scheduler;
+mod settings;
+mod spam;
+mod storage;
+mod targets;
+
+use admin_cache::AdminCache;
 use config::{BotMode, Config};
 use dispatcher::{dispatch, Stores};
-use handlers::moderation::new_warn_store;
-use handlers::filters::new_filter_store;
-use handlers::notes::new_note_store;
+use flood::FloodTracker;
+use roles::Roles;
+use scheduler::Scheduler;
 
 use tgbotrs::{Bot, Poller, UpdateHandler};
 
@@ -67,23 +80,40 @@ async fn main() {
         bot.me.id
     );
 
+    let db = storage::open(&cfg.storage).unwrap_or_else(|e| {
+        eprintln!("❌ Storage error: {:#}", e);
+        eprintln!("💡 Check STORAGE_BACKEND / STORAGE_PATH in .env");
+        std::process::exit(1);
+    });
+
     // ── Register commands ─────────────────────────────────────────────────────
     log::info!("⚙️  Registering bot commands...");
-    match handlers::commands::register_commands(&bot).await {
+    match handlers::commands::register_commands(&bot, cfg.admin_id).await {
         Ok(_) => log::info!("✅ Commands registered"),
         Err(e) => log::warn!("⚠️  Could not register commands: {}", e),
     }
 
+    match cfg.admin_id {
+        Some(id) => log::info!("👑 Bot owner: {}", id),
+        None     => log::warn!("⚠️  ADMIN_ID not set — system commands are disabled"),
+    }
+
+    // ── Start the scheduler ───────────────────────────────────────────────────
+    // Scheduled jobs are logged as the bot, so make sure it has a name.
+    targets::remember(&db, &bot.me);
+    let scheduler = Scheduler::new(db.clone());
+    scheduler.spawn(bot.clone());
+
     // ── Build update handler ──────────────────────────────────────────────────
-    let warn_store   = new_warn_store();
-    let filter_store = new_filter_store();
-    let note_store   = new_note_store();
+    let stores = Stores {
+        db:        db.clone(),
+        admins:    AdminCache::new(cfg.admin_cache_ttl),
+        roles:     Roles::new(cfg.admin_id, db),
+        scheduler,
+        flood:     FloodTracker::new(),
+    };
     let handler: UpdateHandler = Box::new(move |bot, update| {
-        let stores = Stores {
-            warn:   warn_store.clone(),
-            filter: filter_store.clone(),
-            note:   note_store.clone(),
-        };
+        let stores = stores.clone();
         Box::pin(async move {
             dispatch(bot, update, stores).await;
         })
@@ -102,6 +132,7 @@ async fn main() {
             Poller::new(bot, handler)
                 .timeout(cfg.polling.timeout)
                 .limit(cfg.polling.limit)
+                .allowed_updates(allowed_updates())
                 .start()
                 .await
                 .unwrap_or_else(|e| {
@@ -120,7 +151,8 @@ async fn main() {
                 let mut server = WebhookServer::new(bot, handler)
                     .port(cfg.webhook.port)
                     .path(cfg.webhook.path.clone())
-                    .max_connections(cfg.webhook.max_connections);
+                    .max_connections(cfg.webhook.max_connections)
+                    .allowed_updates(allowed_updates());
 
                 if let Some(ref secret) = cfg.webhook.secret {
                     server = server.secret_token(secret.clone());
@@ -149,6 +181,22 @@ async fn main() {
     }
 }
 
+// Telegram leaves chat_member, message_reaction and message_reaction_count
+// out unless they are requested explicitly.
+fn allowed_updates() -> Vec<String> {
+    [
+        "message", "edited_message", "channel_post", "edited_channel_post",
+        "inline_query", "chosen_inline_result", "callback_query",
+        "shipping_query", "pre_checkout_query", "poll", "poll_answer",
+        "my_chat_member", "chat_member", "chat_join_request",
+        "message_reaction", "message_reaction_count",
+        "chat_boost", "removed_chat_boost",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
 fn print_banner() {
     println!();
     println!("  ██████╗ ██╗   ██╗███████╗████████╗ █████╗  ██████╗███████╗");
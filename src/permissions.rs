@@ -0,0 +1,158 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Permission Layer
+//  Checks the caller's ChatMember status and rights before a
+//  privileged handler runs, and refuses to act on the chat creator
+//  or on other admins.
+// ════════════════════════════════════════════════════════════════
+
+use serde_json::{json, Value};
+use tgbotrs::{gen_methods::SendMessageParams, Bot};
+
+use crate::admin_cache::AdminCache;
+
+// ── Rights a command can require ─────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Right {
+    Restrict,
+    Promote,
+    Delete,
+    Pin,
+    ChangeInfo,
+    Invite,
+}
+
+impl Right {
+    /// Field name on `ChatMemberAdministrator`.
+    fn field(self) -> &'static str {
+        match self {
+            Right::Restrict   => "can_restrict_members",
+            Right::Promote    => "can_promote_members",
+            Right::Delete     => "can_delete_messages",
+            Right::Pin        => "can_pin_messages",
+            Right::ChangeInfo => "can_change_info",
+            Right::Invite     => "can_invite_users",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Right::Restrict   => "Ban users",
+            Right::Promote    => "Add new admins",
+            Right::Delete     => "Delete messages",
+            Right::Pin        => "Pin messages",
+            Right::ChangeInfo => "Change group info",
+            Right::Invite     => "Invite users via link",
+        }
+    }
+}
+
+// ── Member snapshot ──────────────────────────────────────────────────────────
+//   `ChatMember` is an untagged enum, so administrators can deserialize into
+//   the owner variant and lose their rights. We read the raw JSON instead.
+
+#[derive(Debug, Clone)]
+pub struct MemberInfo {
+    pub status: String,
+    raw: Value,
+}
+
+impl MemberInfo {
+    pub fn from_value(raw: Value) -> Self {
+        let status = raw.get("status").and_then(|s| s.as_str()).unwrap_or("").to_string();
+        MemberInfo { status, raw }
+    }
+
+    pub fn user_id(&self) -> i64 {
+        self.raw.get("user").and_then(|u| u.get("id")).and_then(|i| i.as_i64()).unwrap_or(0)
+    }
+
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+
+    pub fn is_creator(&self) -> bool {
+        self.status == "creator"
+    }
+
+    pub fn is_admin(&self) -> bool {
+        is_admin_status(&self.status)
+    }
+
+    pub fn has(&self, right: Right) -> bool {
+        if self.is_creator() { return true; }
+        if self.status != "administrator" { return false; }
+        self.raw.get(right.field()).and_then(|b| b.as_bool()).unwrap_or(false)
+    }
+}
+
+pub fn is_admin_status(status: &str) -> bool {
+    status == "creator" || status == "administrator"
+}
+
+/// Uncached single-member lookup (exact status for non-admins too).
+pub async fn fetch_member(bot: &Bot, chat_id: i64, user_id: i64) -> Result<MemberInfo, tgbotrs::BotError> {
+    let raw: Value = bot
+        .call_api("getChatMember", &json!({ "chat_id": chat_id, "user_id": user_id }))
+        .await?;
+    Ok(MemberInfo::from_value(raw))
+}
+
+async fn deny(bot: &Bot, chat_id: i64, text: &str) {
+    let p = SendMessageParams::new().parse_mode("HTML");
+    let _ = bot.send_message(chat_id, text, Some(p)).await;
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Caller check — true if `user_id` holds `right` in `chat_id`.
+//  Sends the denial reply itself.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn require(bot: &Bot, admins: &AdminCache, chat_id: i64, user_id: i64, right: Right) -> bool {
+    match admins.lookup(bot, chat_id, user_id).await {
+        Ok(m) if m.has(right) => true,
+        Ok(m) if m.is_admin() => {
+            deny(bot, chat_id, &format!(
+                "🚫 <b>Permission denied.</b>\n\nYou need the <b>{}</b> admin right to use this command.",
+                right.label())).await;
+            false
+        }
+        Ok(_) => {
+            deny(bot, chat_id,
+                "🚫 <b>Permission denied.</b>\n\nOnly chat admins can use this command.\n\
+                <i>Just promoted? Run /admincache to refresh the admin list.</i>").await;
+            false
+        }
+        Err(e) => {
+            log::warn!("permission check failed in {}: {}", chat_id, e);
+            deny(bot, chat_id, &format!(
+                "❌ <b>Could not verify your permissions:</b> <code>{}</code>", e)).await;
+            false
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Target check — refuses the bot itself, the chat creator and,
+//  unless `allow_admins` is set, other admins.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn guard_target(bot: &Bot, admins: &AdminCache, chat_id: i64, target_id: i64, allow_admins: bool) -> bool {
+    if target_id == bot.me.id {
+        deny(bot, chat_id, "🦀 Nice try — I won't act on myself.").await;
+        return false;
+    }
+    match admins.lookup(bot, chat_id, target_id).await {
+        Ok(m) if m.is_creator() => {
+            deny(bot, chat_id, "🚫 <b>Refused.</b> That user is the chat creator.").await;
+            false
+        }
+        Ok(m) if m.is_admin() && !allow_admins => {
+            deny(bot, chat_id, "🚫 <b>Refused.</b> That user is a chat admin.").await;
+            false
+        }
+        // If the admin list can't be fetched, let the action itself report
+        // the failure — Telegram refuses to restrict admins anyway.
+        _ => true,
+    }
+}
@@ -0,0 +1,397 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Command Registry
+//  Single source of truth for every command: name, aliases,
+//  description, category, required permission and allowed chats.
+//  Dispatch gating, set_my_commands and the /help pages are all
+//  derived from COMMANDS below.
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::types::BotCommand;
+
+use crate::html;
+use crate::permissions::Right;
+use crate::roles::Role;
+
+// ── Spec types ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    General,
+    Fun,
+    Showcase,
+    Media,
+    Moderation,
+    Admin,
+    Filters,
+    Notes,
+    Broadcast,
+    System,
+}
+
+impl Category {
+    pub fn title(self) -> &'static str {
+        match self {
+            Category::General    => "General",
+            Category::Fun        => "Fun",
+            Category::Showcase   => "API Showcase",
+            Category::Media      => "Media",
+            Category::Moderation => "Moderation",
+            Category::Admin      => "Admin",
+            Category::Filters    => "Filters",
+            Category::Notes      => "Notes",
+            Category::Broadcast  => "Send / Post / Media",
+            Category::System     => "System",
+        }
+    }
+}
+
+/// Who may run a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Anyone,
+    /// Chat admin holding this right (see `permissions::require`).
+    Chat(Right),
+    /// Bot-level role (see `roles::require`).
+    Bot(Role),
+}
+
+/// Where a command may run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chats {
+    Any,
+    Groups,
+}
+
+impl Chats {
+    pub fn allows(self, is_private: bool) -> bool {
+        match self {
+            Chats::Any    => true,
+            Chats::Groups => !is_private,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSpec {
+    /// Canonical name, without the leading slash.
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Argument hint shown in help, e.g. `"[duration]"`.
+    pub usage: &'static str,
+    pub description: &'static str,
+    pub category: Category,
+    pub access: Access,
+    pub chats: Chats,
+}
+
+impl CommandSpec {
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+
+    fn help_line(&self) -> String {
+        let mut line = format!("/{}", self.name);
+        if !self.usage.is_empty() {
+            line.push(' ');
+            line.push_str(&html::escape(self.usage));
+        }
+        line.push_str(" — ");
+        line.push_str(&html::escape(self.description));
+        for a in self.aliases {
+            line.push_str(&format!(" <i>(/{})</i>", a));
+        }
+        line
+    }
+}
+
+const fn cmd(
+    name: &'static str,
+    usage: &'static str,
+    description: &'static str,
+    category: Category,
+) -> CommandSpec {
+    CommandSpec { name, aliases: &[], usage, description, category, access: Access::Anyone, chats: Chats::Any }
+}
+
+impl CommandSpec {
+    const fn alias(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    const fn needs(mut self, access: Access) -> Self {
+        self.access = access;
+        self
+    }
+
+    const fn only(mut self, chats: Chats) -> Self {
+        self.chats = chats;
+        self
+    }
+}
+
+use Category::*;
+
+const RESTRICT: Access = Access::Chat(Right::Restrict);
+const PROMOTE:  Access = Access::Chat(Right::Promote);
+const DELETE:   Access = Access::Chat(Right::Delete);
+const PIN:      Access = Access::Chat(Right::Pin);
+const INFO:     Access = Access::Chat(Right::ChangeInfo);
+const INVITE:   Access = Access::Chat(Right::Invite);
+const SUDO:     Access = Access::Bot(Role::Sudo);
+const OWNER:    Access = Access::Bot(Role::Owner);
+
+// ════════════════════════════════════════════════════════════════
+//  The registry
+// ════════════════════════════════════════════════════════════════
+
+pub const COMMANDS: &[CommandSpec] = &[
+    // ── General ───────────────────────────────────────────────────────────────
+    cmd("start",       "", "🦀 Welcome & main menu", General).alias(&["menu"]),
+    cmd("help",        "", "📖 Show all commands", General),
+    cmd("modhelp",     "", "🛡️ Moderation & admin help", General),
+    cmd("about",       "", "ℹ️ About Rustace & tgbotrs", General),
+    cmd("library",     "", "📚 Library overview", General),
+    cmd("stats",       "", "📊 Bot statistics", General),
+    cmd("ping",        "", "🏓 Check bot latency", General),
+    // ── Fun ───────────────────────────────────────────────────────────────────
+    cmd("dice",        "", "🎲 Roll a dice", Fun),
+    cmd("darts",       "", "🎯 Throw darts", Fun),
+    cmd("bowling",     "", "🎳 Play bowling", Fun),
+    cmd("basketball",  "", "🏀 Shoot hoops", Fun),
+    cmd("football",    "", "⚽ Kick the ball", Fun),
+    cmd("slots",       "", "🎰 Slot machine", Fun),
+    cmd("fact",        "", "💡 Random Rust fact", Fun),
+    cmd("joke",        "", "😂 Programmer joke", Fun),
+    cmd("magic8",      "", "🔮 Magic 8-ball", Fun),
+    cmd("coinflip",    "", "🪙 Flip a coin", Fun),
+    // ── API showcase ──────────────────────────────────────────────────────────
+    cmd("botinfo",     "", "🤖 Bot info (get_me)", Showcase),
+    cmd("webhookinfo", "", "📡 Webhook status", Showcase),
+    cmd("membercount", "", "👥 Member count", Showcase),
+    cmd("admins",      "", "👑 List admins", Showcase),
+    cmd("invitelink",  "", "🔗 Get invite link", Showcase),
+    cmd("mycommands",  "", "📋 Show registered commands", Showcase),
+    cmd("myprofile",   "", "👤 Your profile photos", Showcase),
+    // ── Media demos ───────────────────────────────────────────────────────────
+    cmd("photo",       "", "🖼 Demo send_photo", Media),
+    cmd("animation",   "", "🎬 Demo send_animation", Media),
+    cmd("location",    "", "📍 Demo send_location", Media),
+    cmd("venue",       "", "🏢 Demo send_venue", Media),
+    cmd("contact",     "", "📞 Demo send_contact", Media),
+    cmd("poll",        "", "📊 Create a poll", Media),
+    cmd("textstyles",  "", "✨ HTML formatting demo", Media),
+    // ── Moderation ────────────────────────────────────────────────────────────
+    cmd("ban",         "<user> [duration] [reason]", "🔨 Ban user", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("unban",       "<user>", "✅ Unban user", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("kick",        "<user> [reason]", "👢 Kick user", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("mute",        "<user> [duration] [reason]", "🔇 Mute user", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("restrict",    "<user> <preset|perms> [duration] [reason]", "⛓ Take away some send rights", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("unmute",      "<user>", "🔊 Unmute or unrestrict user", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("warn",        "<user> [reason]", "⚠️ Warn user", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("unwarn",      "<user>", "✅ Remove latest warning", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("warns",       "<user>", "📋 List warnings", Moderation).only(Chats::Groups),
+    cmd("resetwarns",  "<user>", "🧹 Clear all warnings", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("warnlimit",   "[n]", "🔢 Warnings before action", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("warnmode",    "[ban|tban|kick|mute] [duration]", "⚙️ Action at warn limit", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("warntime",    "[duration|off]", "⏳ Warning expiry", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("delete",      "", "🗑 Delete replied message", Moderation).alias(&["del"]).needs(DELETE).only(Chats::Groups),
+    cmd("purge",       "[N]", "🧹 Delete from the replied message on", Moderation).needs(DELETE).only(Chats::Groups),
+    cmd("spurge",      "[N]", "🧹 Purge without a notice", Moderation).needs(DELETE).only(Chats::Groups),
+    cmd("pin",         "[duration]", "📌 Pin replied message", Moderation).needs(PIN).only(Chats::Groups),
+    cmd("unpin",       "", "📌 Unpin current message", Moderation).needs(PIN).only(Chats::Groups),
+    cmd("ro",          "[duration]", "🔇 Read-only mode ON", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("unro",        "", "🔊 Read-only mode OFF", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("pending",     "", "⏰ Scheduled actions", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("lock",        "<type…|all>", "🔒 Lock a content type", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("unlock",      "<type…|all>", "🔓 Unlock a content type", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("locks",       "", "🔐 Show content locks", Moderation).only(Chats::Groups),
+    cmd("blocklist",   "", "🚫 Show blocklisted words", Moderation).only(Chats::Groups),
+    cmd("addblock",    "<pattern>", "🚫 Blocklist a word or pattern", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("rmblock",     "<pattern>", "🗑 Remove a blocklist pattern", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("blockmode",   "<action>", "⚙️ Action for blocklisted words", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("flood",       "", "🌊 Show antiflood settings", Moderation).only(Chats::Groups),
+    cmd("setflood",    "<n> [window]|off", "🌊 Messages per window before acting", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("floodmode",   "<action>", "⚙️ Action for flooders", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("antiraid",    "[duration|off]", "🛡 Anti-raid mode", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("raidmode",    "[kick|mute]", "⚙️ What anti-raid does to joins", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("autoraid",    "<joins> [window]|off", "🚨 Join rate that starts anti-raid", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("antispam",    "[on|off]", "🧪 Score new members' first messages", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("spamlimits",  "<delete> <mute> <ban>", "🧪 Scores that delete, mute or ban", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("spamscore",   "", "🧪 Show how the replied message scores", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("approve",     "<user>", "👍 Exempt from automated enforcement", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("unapprove",   "<user>", "👎 Remove an approval", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("approved",    "", "✅ List approved users", Moderation).only(Chats::Groups),
+    cmd("report",      "[reason]", "🚩 Report the replied message to admins", Moderation).only(Chats::Groups),
+    cmd("captcha",     "[on|off|button|math|emoji]", "🧩 Captcha for new members", Moderation).needs(RESTRICT).only(Chats::Groups),
+    cmd("captchatime", "[duration]", "⏳ Time to solve the captcha", Moderation).needs(RESTRICT).only(Chats::Groups),
+    // ── Admin ─────────────────────────────────────────────────────────────────
+    cmd("promote",     "<user> [title]", "⭐ Promote user", Admin).needs(PROMOTE).only(Chats::Groups),
+    cmd("demote",      "<user>", "🔽 Demote user", Admin).needs(PROMOTE).only(Chats::Groups),
+    cmd("title",       "<user> <title>", "🏷️ Set admin title", Admin).needs(PROMOTE).only(Chats::Groups),
+    cmd("userinfo",    "<user>", "👤 User info", Admin).alias(&["whois"]).only(Chats::Groups),
+    cmd("admincache",  "", "🔄 Refresh cached admin list", Admin).only(Chats::Groups),
+    cmd("joinpolicy",  "[approve|decline|review]", "🚪 Handling of join requests", Admin).needs(INVITE).only(Chats::Groups),
+    cmd("joinquestions", "[q1 | q2 …|off]", "📝 Questionnaire for applicants", Admin).needs(INVITE).only(Chats::Groups),
+    cmd("setwelcome",  "<text|off>", "👋 Welcome message template", Admin).needs(INFO).only(Chats::Groups),
+    cmd("setgoodbye",  "<text|off>", "👋 Goodbye message template", Admin).needs(INFO).only(Chats::Groups),
+    cmd("cleanwelcome", "[on|off]", "🧹 Delete the previous welcome", Admin).needs(INFO).only(Chats::Groups),
+    cmd("cleanservice", "[on|off]", "🧹 Delete join/leave messages", Admin).needs(INFO).only(Chats::Groups),
+    cmd("gbanstat",    "[on|off]", "🌐 Enforce the global ban list here", Admin).needs(RESTRICT).only(Chats::Groups),
+    cmd("reportpm",    "[on|off]", "🚩 Get this chat's reports in private", Admin).only(Chats::Groups),
+    cmd("modlog",      "[user]", "📋 Recent moderation actions", Admin).needs(RESTRICT).only(Chats::Groups),
+    cmd("setlog",      "[channel|off]", "📡 Log channel for mod actions", Admin).needs(INFO).only(Chats::Groups),
+    // ── Filters ───────────────────────────────────────────────────────────────
+    cmd("filter",      "<keyword> <reply>", "🔑 Add keyword auto-reply", Filters),
+    cmd("delfilter",   "<keyword>", "🗑 Delete a filter", Filters),
+    cmd("filters",     "", "📋 List all active filters", Filters),
+    // ── Notes ─────────────────────────────────────────────────────────────────
+    cmd("note",        "<name> <text>", "📝 Save a note", Notes),
+    cmd("get",         "<name>", "📌 Get a saved note", Notes),
+    cmd("notes",       "", "📋 List all saved notes", Notes),
+    cmd("delnote",     "<name>", "🗑 Delete a note", Notes),
+    // ── Send / Post / Media ───────────────────────────────────────────────────
+    cmd("send",        "<text>", "📨 Send message with inline buttons", Broadcast),
+    cmd("post",        "<text>", "📢 Styled broadcast with buttons", Broadcast),
+    cmd("img",         "<url>", "🖼 Send photo from URL", Broadcast),
+    cmd("vid",         "<url>", "🎬 Send video from URL", Broadcast),
+    cmd("aud",         "<url>", "🎵 Send audio from URL", Broadcast),
+    cmd("doc",         "<url>", "📁 Send document from URL", Broadcast),
+    cmd("buttons",     "", "🎨 Colourful button showcase", Broadcast),
+    cmd("sendhelp",    "", "📡 /send and /post guide", Broadcast),
+    // ── System ────────────────────────────────────────────────────────────────
+    cmd("setcommands",    "", "⚙️ Register commands", System).needs(SUDO),
+    cmd("deletecommands", "", "🗑 Delete commands", System).needs(SUDO),
+    cmd("deletewebhook",  "", "🧹 Remove webhook", System).needs(SUDO),
+    cmd("sudolist",       "", "🔐 Show owner & sudo users", System).needs(SUDO),
+    cmd("gban",           "<user> <reason>", "🌐 Ban in every opted-in chat", System).needs(SUDO),
+    cmd("ungban",         "<user>", "✅ Lift a global ban", System).needs(SUDO),
+    cmd("addsudo",        "<user>", "🛡️ Grant sudo", System).needs(OWNER),
+    cmd("rmsudo",         "<user>", "🔽 Revoke sudo", System).needs(OWNER),
+];
+
+// ── Menu translations ────────────────────────────────────────────────────────
+//   Optional per-`language_code` descriptions. Commands missing from a
+//   language fall back to the English description above.
+
+pub const TRANSLATIONS: &[(&str, &[(&str, &str)])] = &[
+    ("es", &[
+        ("start",    "🦀 Bienvenida y menú principal"),
+        ("help",     "📖 Ver todos los comandos"),
+        ("modhelp",  "🛡️ Ayuda de moderación"),
+        ("about",    "ℹ️ Acerca de Rustace y tgbotrs"),
+        ("stats",    "📊 Estadísticas del bot"),
+        ("ping",     "🏓 Medir la latencia"),
+        ("ban",      "🔨 Banear usuario"),
+        ("kick",     "👢 Expulsar usuario"),
+        ("mute",     "🔇 Silenciar usuario"),
+        ("warn",     "⚠️ Advertir usuario"),
+    ]),
+    ("ru", &[
+        ("start",    "🦀 Приветствие и главное меню"),
+        ("help",     "📖 Все команды"),
+        ("modhelp",  "🛡️ Справка по модерации"),
+        ("about",    "ℹ️ О Rustace и tgbotrs"),
+        ("stats",    "📊 Статистика бота"),
+        ("ping",     "🏓 Проверить задержку"),
+        ("ban",      "🔨 Забанить"),
+        ("kick",     "👢 Выгнать"),
+        ("mute",     "🔇 Заглушить"),
+        ("warn",     "⚠️ Предупреждение"),
+    ]),
+];
+
+// ── Menus ────────────────────────────────────────────────────────────────────
+//   One command list per BotCommandScope. Each menu only shows what its
+//   audience can actually run.
+
+/// Most commands `setMyCommands` accepts for one scope.
+pub const MAX_MENU: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Menu {
+    /// Fallback for any scope without its own list.
+    Default,
+    Private,
+    Groups,
+    /// Group admins — the commands gated on a chat right. Commands anyone
+    /// can run are in the group menus.
+    Admins,
+    /// The owner's private chat — private commands plus system commands.
+    Owner,
+}
+
+impl Menu {
+    fn includes(self, c: &CommandSpec) -> bool {
+        match self {
+            Menu::Default => c.access == Access::Anyone && c.chats == Chats::Any,
+            Menu::Private => c.access == Access::Anyone && c.chats.allows(true),
+            Menu::Groups  => c.access == Access::Anyone && c.chats.allows(false),
+            Menu::Admins  => matches!(c.access, Access::Chat(_)) && c.chats.allows(false),
+            Menu::Owner   => !matches!(c.access, Access::Chat(_)) && c.chats.allows(true),
+        }
+    }
+}
+
+// ── Lookups ──────────────────────────────────────────────────────────────────
+
+/// Finds a command by name or alias (without the leading slash).
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|c| c.matches(name))
+}
+
+/// Language codes with at least one translated description.
+pub fn languages() -> impl Iterator<Item = &'static str> {
+    TRANSLATIONS.iter().map(|(lang, _)| *lang)
+}
+
+fn describe(c: &CommandSpec, lang: Option<&str>) -> &'static str {
+    lang.and_then(|l| TRANSLATIONS.iter().find(|(code, _)| *code == l))
+        .and_then(|(_, table)| table.iter().find(|(name, _)| *name == c.name))
+        .map(|(_, d)| *d)
+        .unwrap_or(c.description)
+}
+
+/// Entries for `set_my_commands` in `menu`, described in `lang` where a
+/// translation exists. Capped at `MAX_MENU`, which Telegram enforces.
+pub fn bot_commands(menu: Menu, lang: Option<&str>) -> Vec<BotCommand> {
+    let mut list: Vec<BotCommand> = COMMANDS
+        .iter()
+        .filter(|c| menu.includes(c))
+        .map(|c| BotCommand { command: c.name.into(), description: describe(c, lang).into() })
+        .collect();
+    debug_assert!(list.len() <= MAX_MENU, "{:?} menu has {} commands", menu, list.len());
+    if list.len() > MAX_MENU {
+        log::warn!("{:?} menu has {} commands; only the first {} are shown", menu, list.len(), MAX_MENU);
+        list.truncate(MAX_MENU);
+    }
+    list
+}
+
+/// HTML help section for each category, in registry order.
+pub fn help_sections(categories: &[Category]) -> String {
+    categories
+        .iter()
+        .map(|&cat| {
+            let lines: Vec<String> = COMMANDS.iter().filter(|c| c.category == cat).map(|c| c.help_line()).collect();
+            format!("<b>{}</b>\n{}", cat.title(), lines.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_menu_fits_telegram_limit() {
+        for menu in [Menu::Default, Menu::Private, Menu::Groups, Menu::Admins, Menu::Owner] {
+            let n = COMMANDS.iter().filter(|c| menu.includes(c)).count();
+            assert!(n <= MAX_MENU, "{:?} menu has {} commands", menu, n);
+            for lang in std::iter::once(None).chain(languages().map(Some)) {
+                assert_eq!(bot_commands(menu, lang).len(), n);
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Bot-level Roles
+//  One owner (ADMIN_ID from config) plus a sudo list the owner
+//  manages with /addsudo and /rmsudo. Gates system commands.
+// ════════════════════════════════════════════════════════════════
+
+use tgbotrs::{gen_methods::SendMessageParams, Bot};
+
+use crate::storage::{Db, SUDO};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    User,
+    Sudo,
+    Owner,
+}
+
+impl Role {
+    fn label(self) -> &'static str {
+        match self {
+            Role::User  => "user",
+            Role::Sudo  => "sudo",
+            Role::Owner => "owner",
+        }
+    }
+}
+
+/// Owner from config; sudo users persisted in the `sudo` table.
+#[derive(Clone)]
+pub struct Roles {
+    owner: Option<i64>,
+    db: Db,
+}
+
+impl Roles {
+    pub fn new(owner: Option<i64>, db: Db) -> Self {
+        Roles { owner, db }
+    }
+
+    pub fn owner(&self) -> Option<i64> {
+        self.owner
+    }
+
+    pub fn role_of(&self, user_id: i64) -> Role {
+        if self.owner == Some(user_id) {
+            Role::Owner
+        } else if self.db.get(SUDO, &user_id.to_string()).is_some() {
+            Role::Sudo
+        } else {
+            Role::User
+        }
+    }
+
+    /// Returns false if the user was already sudo (or is the owner).
+    pub fn add_sudo(&self, user_id: i64) -> bool {
+        if self.owner == Some(user_id) { return false; }
+        let mut added = false;
+        self.db.update(SUDO, &user_id.to_string(), &mut |old| {
+            added = old.is_none();
+            Some(old.unwrap_or(serde_json::Value::Bool(true)))
+        });
+        added
+    }
+
+    pub fn remove_sudo(&self, user_id: i64) -> bool {
+        self.db.delete(SUDO, &user_id.to_string())
+    }
+
+    pub fn sudo_list(&self) -> Vec<i64> {
+        let mut ids: Vec<i64> = self.db.scan(SUDO, "")
+            .into_iter()
+            .filter_map(|(k, _)| k.parse().ok())
+            .collect();
+        ids.sort();
+        ids
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Gate — true if `user_id` holds at least `needed`.
+//  Denials are logged and answered in the chat.
+// ════════════════════════════════════════════════════════════════
+
+pub async fn require(bot: &Bot, roles: &Roles, chat_id: i64, user_id: i64, needed: Role, command: &str) -> bool {
+    let role = roles.role_of(user_id);
+    if role >= needed { return true; }
+
+    log::warn!(
+        "unauthorised {} by user {} in chat {} (role={}, needs {})",
+        command, user_id, chat_id, role.label(), needed.label()
+    );
+    let text = if roles.owner().is_none() {
+        "🔒 <b>System commands are disabled.</b>\n\n<i>Set ADMIN_ID in .env to name a bot owner.</i>".to_string()
+    } else {
+        format!("🔒 <b>Restricted.</b>\n\n<code>{}</code> needs the bot <b>{}</b> role.", command, needed.label())
+    };
+    let p = SendMessageParams::new().parse_mode("HTML");
+    let _ = bot.send_message(chat_id, text, Some(p)).await;
+    false
+}
@@ -0,0 +1,175 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Scheduler
+//  Persistent queue of timed moderation actions in the `schedule`
+//  table, worked off by one tokio task. Entries survive restarts;
+//  anything that came due while the bot was down runs on startup.
+//  Examples: /ro 2h lifting itself, /pin 1d unpinning, and expiry
+//  notices in the audit log when a temporary ban or mute ends.
+// ════════════════════════════════════════════════════════════════
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{gen_methods::{SendMessageParams, UnpinChatMessageParams}, Bot, ChatId};
+use tokio::sync::Notify;
+
+use crate::handlers::audit::{self, Action, Entry};
+use crate::handlers::{antiraid, captcha};
+use crate::duration::now;
+use crate::handlers::locks;
+use crate::html;
+use crate::storage::{chat_key, chat_prefix, Db, SCHEDULE};
+use crate::targets::Target;
+
+/// Upper bound on how long the worker sleeps without re-reading the queue.
+const IDLE_POLL: i64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    /// End of a timed `/ro`: restore member permissions.
+    ReadOnlyEnd,
+    Unpin { message_id: i64 },
+    /// Telegram lifts these itself; the job only logs the expiry.
+    BanExpired { user_id: i64, name: String },
+    MuteExpired { user_id: i64, name: String },
+    /// New member's captcha deadline; kicks them if still unsolved.
+    CaptchaTimeout { user_id: i64, name: String },
+    /// End of anti-raid mode.
+    RaidEnd,
+}
+
+impl Job {
+    /// One pending job per slot and chat; scheduling again replaces it.
+    fn slot(&self) -> String {
+        match self {
+            Job::ReadOnlyEnd                 => "ro".to_string(),
+            Job::Unpin { message_id }        => format!("unpin:{}", message_id),
+            Job::BanExpired { user_id, .. }  => format!("ban:{}", user_id),
+            Job::MuteExpired { user_id, .. } => format!("mute:{}", user_id),
+            Job::CaptchaTimeout { user_id, .. } => format!("captcha:{}", user_id),
+            Job::RaidEnd                     => "raid".to_string(),
+        }
+    }
+
+    /// Short HTML description for /pending.
+    pub fn describe(&self) -> String {
+        match self {
+            Job::ReadOnlyEnd          => "🔊 Read-only mode ends".to_string(),
+            Job::Unpin { message_id } => format!("📍 Unpin message <code>{}</code>", message_id),
+            Job::BanExpired { user_id, name } => format!(
+                "✅ Ban expires for <a href=\"tg://user?id={}\">{}</a>", user_id, html::escape(name)),
+            Job::MuteExpired { user_id, name } => format!(
+                "🔊 Mute expires for <a href=\"tg://user?id={}\">{}</a>", user_id, html::escape(name)),
+            Job::CaptchaTimeout { user_id, name } => format!(
+                "🧩 Captcha deadline for <a href=\"tg://user?id={}\">{}</a>", user_id, html::escape(name)),
+            Job::RaidEnd => "🛡 Anti-raid mode ends".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub chat_id: i64,
+    /// Unix time the job runs at.
+    pub at: i64,
+    pub job: Job,
+}
+
+#[derive(Clone)]
+pub struct Scheduler {
+    db: Db,
+    wake: Arc<Notify>,
+}
+
+impl Scheduler {
+    pub fn new(db: Db) -> Self {
+        Scheduler { db, wake: Arc::new(Notify::new()) }
+    }
+
+    /// Queues `job` to run `secs` from now, replacing any job in the same slot.
+    pub fn schedule(&self, chat_id: i64, secs: i64, job: Job) {
+        let task = Task { chat_id, at: now() + secs, job };
+        self.db.save(SCHEDULE, &chat_key(chat_id, task.job.slot()), &task);
+        self.wake.notify_one();
+    }
+
+    /// Drops the pending job in `job`'s slot, if any.
+    pub fn cancel(&self, chat_id: i64, job: &Job) {
+        self.db.delete(SCHEDULE, &chat_key(chat_id, job.slot()));
+    }
+
+    /// Pending tasks for one chat, soonest first.
+    pub fn pending(&self, chat_id: i64) -> Vec<Task> {
+        let mut tasks: Vec<Task> = self.db.load_all::<Task>(SCHEDULE, &chat_prefix(chat_id))
+            .into_iter().map(|(_, t)| t).collect();
+        tasks.sort_by_key(|t| t.at);
+        tasks
+    }
+
+    /// Starts the worker. Call once, after storage is open.
+    pub fn spawn(&self, bot: Bot) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let now = now();
+                let mut next = now + IDLE_POLL;
+                for (key, task) in this.db.load_all::<Task>(SCHEDULE, "") {
+                    if task.at <= now {
+                        // Removed first so a failing job can't loop forever.
+                        this.db.delete(SCHEDULE, &key);
+                        run(&bot, &this.db, task).await;
+                    } else {
+                        next = next.min(task.at);
+                    }
+                }
+                let wait = Duration::from_secs((next - now).max(1) as u64);
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = this.wake.notified()     => {}
+                }
+            }
+        });
+    }
+}
+
+// ── Job execution ────────────────────────────────────────────────────────────
+
+async fn run(bot: &Bot, db: &Db, task: Task) {
+    let chat_id = task.chat_id;
+    let me = bot.me.id;
+    log::info!("scheduler: running {:?} in {}", task.job, chat_id);
+
+    match task.job {
+        Job::ReadOnlyEnd => {
+            match bot.set_chat_permissions(ChatId::from(chat_id), locks::member_perms(db, chat_id), None).await {
+                Ok(_) => {
+                    audit::record(bot, db, Entry::new(chat_id, me, Action::Unro).reason("timed read-only ended")).await;
+                    let p = SendMessageParams::new().parse_mode("HTML");
+                    let _ = bot.send_message(chat_id,
+                        "🔊 <b>Read-only mode OFF</b>\n\nThe timer ran out — all members can send messages again.",
+                        Some(p)).await;
+                }
+                Err(e) => log::warn!("scheduler: could not lift read-only in {}: {}", chat_id, e),
+            }
+        }
+        Job::Unpin { message_id } => {
+            let p = UnpinChatMessageParams::new().message_id(message_id);
+            match bot.unpin_chat_message(ChatId::from(chat_id), Some(p)).await {
+                Ok(_)  => audit::record(bot, db, Entry::new(chat_id, me, Action::Unpin).reason("timed pin ended")).await,
+                Err(e) => log::warn!("scheduler: could not unpin {} in {}: {}", message_id, chat_id, e),
+            }
+        }
+        Job::BanExpired { user_id, name } => {
+            let t = Target { id: user_id, name };
+            audit::record(bot, db, Entry::new(chat_id, me, Action::Unban).target(&t).reason("temporary ban expired")).await;
+        }
+        Job::MuteExpired { user_id, name } => {
+            let t = Target { id: user_id, name };
+            audit::record(bot, db, Entry::new(chat_id, me, Action::Unmute).target(&t).reason("temporary mute expired")).await;
+        }
+        Job::CaptchaTimeout { user_id, .. } => captcha::expire(bot, chat_id, user_id, db).await,
+        Job::RaidEnd => antiraid::end(bot, chat_id, me, "the timer ran out", db).await,
+    }
+}
@@ -0,0 +1,248 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Per-chat Settings
+//  One JSON document per chat in the `settings` table. Every field
+//  has a serde default, so documents written before a setting
+//  existed keep loading unchanged.
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{Db, SETTINGS};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatSettings {
+    pub warn: WarnPolicy,
+    pub captcha: CaptchaPolicy,
+    pub join: JoinPolicy,
+    pub greetings: Greetings,
+    pub locks: Vec<Lock>,
+    pub blocklist: BlocklistAction,
+    pub flood: FloodPolicy,
+    pub raid: RaidPolicy,
+    pub spam: SpamPolicy,
+    /// Whether users on the global ban list are removed here (opt-in).
+    pub gbans: bool,
+    /// Channel that receives a copy of every audit-log entry.
+    pub log_channel: Option<i64>,
+}
+
+// ── Warnings ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarnPolicy {
+    /// Warnings that trigger `action`.
+    pub limit: u8,
+    pub action: WarnAction,
+    /// Seconds after which a warning stops counting; `None` = never.
+    pub expiry: Option<i64>,
+}
+
+impl Default for WarnPolicy {
+    fn default() -> Self {
+        WarnPolicy { limit: 3, action: WarnAction::Ban, expiry: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum WarnAction {
+    Ban,
+    Tban { secs: i64 },
+    Kick,
+    /// `None` = until unmuted.
+    Mute { secs: Option<i64> },
+}
+
+// ── Captcha ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptchaPolicy {
+    pub enabled: bool,
+    pub mode: CaptchaMode,
+    /// Seconds a new member has to solve it before being kicked.
+    pub timeout: i64,
+}
+
+impl Default for CaptchaPolicy {
+    fn default() -> Self {
+        CaptchaPolicy { enabled: false, mode: CaptchaMode::Button, timeout: 300 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaMode {
+    /// Press "I'm human".
+    Button,
+    /// Pick the result of a small sum.
+    Math,
+    /// Pick the named emoji.
+    Emoji,
+}
+
+// ── Join requests ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JoinPolicy {
+    pub mode: JoinMode,
+    /// Sent to applicants in private when reviewing; empty = no questionnaire.
+    pub questions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinMode {
+    #[default]
+    Approve,
+    Decline,
+    /// Post a review card for admins.
+    Review,
+}
+
+// ── Welcome / goodbye ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Greetings {
+    /// Template with `{placeholders}` and `[Label | value]` button lines; `None` = off.
+    pub welcome: Option<String>,
+    pub goodbye: Option<String>,
+    /// Delete the previous welcome when a new one is sent.
+    pub clean_welcome: bool,
+    /// Delete the "X joined" / "X left" service messages.
+    pub clean_service: bool,
+    /// Last welcome sent, for `clean_welcome`.
+    pub last_welcome: Option<i64>,
+}
+
+// ── Locks ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lock {
+    Url,
+    Forward,
+    /// Forwards whose origin is a channel.
+    ChannelForward,
+    Sticker,
+    Gif,
+    Photo,
+    Video,
+    Voice,
+    Poll,
+    Contact,
+    /// Messages sent `via` an inline bot.
+    Inline,
+    /// `@somebot` mentions.
+    BotMention,
+    /// Messages sent as a channel rather than as a user.
+    AsChannel,
+}
+
+// ── Blocklist ────────────────────────────────────────────────────────────────
+
+/// What happens to the sender of a blocklisted message. The message itself
+/// is always deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlocklistAction {
+    #[default]
+    Delete,
+    Warn,
+    /// Ban, kick or mute, as for the warn limit.
+    Punish(WarnAction),
+}
+
+// ── Antiflood ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FloodPolicy {
+    /// Messages within `window` that count as flooding; 0 = off.
+    pub limit: u32,
+    /// Seconds.
+    pub window: i64,
+    pub action: WarnAction,
+}
+
+impl Default for FloodPolicy {
+    fn default() -> Self {
+        FloodPolicy { limit: 0, window: 10, action: WarnAction::Mute { secs: Some(60 * 60) } }
+    }
+}
+
+// ── Anti-raid ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RaidPolicy {
+    pub action: RaidAction,
+    /// Seconds anti-raid stays on when started without a duration.
+    pub duration: i64,
+    /// Joins within `auto_window` seconds that switch it on; 0 = manual only.
+    pub auto_joins: u32,
+    pub auto_window: i64,
+    /// Unix time the active raid mode ends; `None` = off.
+    pub until: Option<i64>,
+    /// Joins blocked during the current raid mode.
+    pub caught: u32,
+}
+
+impl Default for RaidPolicy {
+    fn default() -> Self {
+        RaidPolicy { action: RaidAction::Kick, duration: 6 * 60 * 60, auto_joins: 0, auto_window: 60, until: None, caught: 0 }
+    }
+}
+
+impl RaidPolicy {
+    pub fn active(&self, now: i64) -> bool {
+        self.until.is_some_and(|u| u > now)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RaidAction {
+    Kick,
+    /// Muted until raid mode ends.
+    Mute,
+}
+
+// ── Spam scoring ─────────────────────────────────────────────────────────────
+
+/// Score thresholds for new members' first messages (see spam.rs);
+/// 0 switches a step off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpamPolicy {
+    pub enabled: bool,
+    pub delete: u32,
+    pub mute: u32,
+    pub ban: u32,
+}
+
+impl Default for SpamPolicy {
+    fn default() -> Self {
+        SpamPolicy { enabled: false, delete: 40, mute: 60, ban: 90 }
+    }
+}
+
+// ── Access ───────────────────────────────────────────────────────────────────
+
+pub fn load(db: &Db, chat_id: i64) -> ChatSettings {
+    db.load(SETTINGS, &chat_id.to_string()).unwrap_or_default()
+}
+
+/// Read-modify-write of one chat's settings; returns the new value.
+pub fn update(db: &Db, chat_id: i64, f: impl FnOnce(&mut ChatSettings)) -> ChatSettings {
+    db.modify::<ChatSettings, _>(SETTINGS, &chat_id.to_string(), |old| {
+        let mut s = old.unwrap_or_default();
+        f(&mut s);
+        Some(s)
+    })
+    .unwrap_or_default()
+}
@@ -0,0 +1,314 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Spam Scoring
+//  Scores a new member's early messages from local signals only:
+//  how soon after joining they post, links, invite links, channel
+//  forwards, the sender's name, emoji density and repeated text.
+//  Plain functions over plain data — no Telegram calls, so every
+//  rule can be checked in isolation. Enforcement lives in
+//  handlers/antispam.rs.
+// ════════════════════════════════════════════════════════════════
+
+use crate::duration;
+use crate::settings::SpamPolicy;
+
+/// Invite links to other chats; matched against lowercased text.
+const INVITE_MARKERS: &[&str] = &["t.me/+", "t.me/joinchat", "telegram.me/+", "telegram.me/joinchat", "tg://join"];
+/// Name fragments common in spam accounts; matched against the lowercased name.
+const NAME_LINKS: &[&str] = &["http", "t.me", "www.", ".com", "@"];
+const NAME_WORDS: &[&str] = &[
+    "crypto", "bitcoin", "btc", "invest", "forex", "profit", "casino",
+    "airdrop", "promo", "dating", "18+", "xxx",
+];
+/// Emoji needed before density counts at all.
+const MIN_EMOJI: usize = 6;
+/// Shortest text that counts as repeated.
+const MIN_REPEAT_LEN: usize = 10;
+
+/// What is known about one message and its sender.
+#[derive(Debug, Clone, Default)]
+pub struct Post<'a> {
+    /// Seconds between joining and posting.
+    pub since_join: i64,
+    /// Text or caption.
+    pub text: &'a str,
+    /// URLs from the message's entities.
+    pub links: &'a [String],
+    pub channel_forward: bool,
+    /// First and last name of the sender.
+    pub name: &'a str,
+    /// The sender's earlier scored messages, as returned by `normalize`.
+    pub earlier: &'a [String],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    FreshJoin,
+    Link,
+    InviteLink,
+    ChannelForward,
+    Name,
+    Emoji,
+    Repeated,
+}
+
+impl Signal {
+    pub fn label(self) -> &'static str {
+        match self {
+            Signal::FreshJoin      => "posted right after joining",
+            Signal::Link           => "links",
+            Signal::InviteLink     => "invite link",
+            Signal::ChannelForward => "forwarded from a channel",
+            Signal::Name           => "suspicious name",
+            Signal::Emoji          => "emoji-heavy",
+            Signal::Repeated       => "repeated text",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Score {
+    pub total: u32,
+    /// Every signal that fired with the points it added.
+    pub hits: Vec<(Signal, u32)>,
+}
+
+impl Score {
+    fn add(&mut self, signal: Signal, points: u32) {
+        if points == 0 { return; }
+        self.total += points;
+        self.hits.push((signal, points));
+    }
+
+    /// "links +20, invite link +30".
+    pub fn describe(&self) -> String {
+        self.hits.iter().map(|(s, p)| format!("{} +{}", s.label(), p)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// What to do with a message, mildest first. The message is deleted in
+/// every case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Delete,
+    Mute,
+    Ban,
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Signals
+// ════════════════════════════════════════════════════════════════
+
+fn fresh_join(since_join: i64) -> u32 {
+    match since_join {
+        s if s < duration::MINUTE      => 25,
+        s if s < 10 * duration::MINUTE => 15,
+        s if s < duration::HOUR        => 5,
+        _ => 0,
+    }
+}
+
+/// 15 for the first link, 5 for each further one, up to 30.
+fn links(count: usize) -> u32 {
+    match count {
+        0 => 0,
+        n => (15 + 5 * (n as u32 - 1)).min(30),
+    }
+}
+
+fn has_invite(text: &str, links: &[String]) -> bool {
+    let text = text.to_lowercase();
+    let found = |s: &str| INVITE_MARKERS.iter().any(|m| s.contains(m));
+    found(&text) || links.iter().any(|l| found(&l.to_lowercase()))
+}
+
+/// A link or spam keyword in the name, or no letters at all.
+pub fn suspicious_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    NAME_LINKS.iter().chain(NAME_WORDS).any(|w| lower.contains(w))
+        || (!name.trim().is_empty() && !name.chars().any(char::is_alphabetic))
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+/// At least `MIN_EMOJI` emoji making up a third or more of the visible
+/// characters.
+pub fn emoji_heavy(text: &str) -> bool {
+    let visible = text.chars().filter(|c| !c.is_whitespace() && *c != '\u{fe0f}' && *c != '\u{200d}').count();
+    let emoji = text.chars().filter(|c| is_emoji(*c)).count();
+    emoji >= MIN_EMOJI && emoji * 3 >= visible
+}
+
+/// Lowercased with whitespace collapsed, for comparing messages.
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn repeated(text: &str, earlier: &[String]) -> bool {
+    let text = normalize(text);
+    text.chars().count() >= MIN_REPEAT_LEN && earlier.contains(&text)
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Scoring
+// ════════════════════════════════════════════════════════════════
+
+pub fn score(post: &Post) -> Score {
+    let mut s = Score::default();
+    s.add(Signal::FreshJoin, fresh_join(post.since_join));
+    s.add(Signal::Link, links(post.links.len()));
+    if has_invite(post.text, post.links) { s.add(Signal::InviteLink, 30); }
+    if post.channel_forward { s.add(Signal::ChannelForward, 30); }
+    if suspicious_name(post.name) { s.add(Signal::Name, 15); }
+    if emoji_heavy(post.text) { s.add(Signal::Emoji, 15); }
+    if repeated(post.text, post.earlier) { s.add(Signal::Repeated, 20); }
+    s
+}
+
+/// The harshest step whose threshold `total` reaches; a threshold of 0
+/// is off.
+pub fn verdict(total: u32, policy: &SpamPolicy) -> Option<Verdict> {
+    [(Verdict::Ban, policy.ban), (Verdict::Mute, policy.mute), (Verdict::Delete, policy.delete)]
+        .into_iter()
+        .find(|(_, at)| *at > 0 && total >= *at)
+        .map(|(v, _)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A post by an ordinary member a day after joining; scores nothing.
+    fn post(text: &str) -> Post<'_> {
+        Post { since_join: duration::DAY, text, name: "Alice", ..Post::default() }
+    }
+
+    fn signals(post: &Post) -> Vec<Signal> {
+        score(post).hits.into_iter().map(|(s, _)| s).collect()
+    }
+
+    #[test]
+    fn quiet_post_scores_nothing() {
+        assert_eq!(score(&post("hello everyone")), Score::default());
+    }
+
+    #[test]
+    fn join_age() {
+        assert_eq!(fresh_join(0), 25);
+        assert_eq!(fresh_join(duration::MINUTE - 1), 25);
+        assert_eq!(fresh_join(duration::MINUTE), 15);
+        assert_eq!(fresh_join(10 * duration::MINUTE - 1), 15);
+        assert_eq!(fresh_join(10 * duration::MINUTE), 5);
+        assert_eq!(fresh_join(duration::HOUR - 1), 5);
+        assert_eq!(fresh_join(duration::HOUR), 0);
+        let p = Post { since_join: 5, ..post("hi") };
+        assert_eq!(score(&p).hits, vec![(Signal::FreshJoin, 25)]);
+    }
+
+    #[test]
+    fn links_add_up_to_a_cap() {
+        assert_eq!(links(0), 0);
+        assert_eq!(links(1), 15);
+        assert_eq!(links(2), 20);
+        assert_eq!(links(4), 30);
+        assert_eq!(links(50), 30);
+        let urls = vec!["https://example.com".to_string()];
+        let p = Post { links: &urls, ..post("see https://example.com") };
+        assert_eq!(signals(&p), vec![Signal::Link]);
+    }
+
+    #[test]
+    fn invite_links() {
+        assert!(has_invite("join t.me/+AbCdEf now", &[]));
+        assert!(has_invite("T.ME/JOINCHAT/xyz", &[]));
+        assert!(has_invite("click here", &["https://telegram.me/+abc".to_string()]));
+        assert!(has_invite("", &["tg://join?invite=abc".to_string()]));
+        assert!(!has_invite("t.me/rustlang", &["https://t.me/rustlang".to_string()]));
+        let urls = vec!["https://t.me/+abc".to_string()];
+        let p = Post { links: &urls, ..post("free signals") };
+        assert_eq!(score(&p).total, 15 + 30);
+        assert_eq!(signals(&p), vec![Signal::Link, Signal::InviteLink]);
+    }
+
+    #[test]
+    fn channel_forwards() {
+        let p = Post { channel_forward: true, ..post("big news") };
+        assert_eq!(score(&p).hits, vec![(Signal::ChannelForward, 30)]);
+    }
+
+    #[test]
+    fn name_patterns() {
+        assert!(suspicious_name("Crypto Signals"));
+        assert!(suspicious_name("Anna t.me/annabot"));
+        assert!(suspicious_name("Bob @promo_channel"));
+        assert!(suspicious_name("Free BTC"));
+        assert!(suspicious_name("💰💰💰"));
+        assert!(suspicious_name("12345"));
+        assert!(!suspicious_name("Alice"));
+        assert!(!suspicious_name("Jean-Luc Picard"));
+        assert!(!suspicious_name("Владимир"));
+        assert!(!suspicious_name(""));
+        let p = Post { name: "Forex Profit", ..post("hi") };
+        assert_eq!(score(&p).hits, vec![(Signal::Name, 15)]);
+    }
+
+    #[test]
+    fn emoji_density() {
+        assert!(emoji_heavy("🔥🔥🔥 💰💰💰"));
+        assert!(emoji_heavy("🚀🚀🚀🚀🚀🚀 go now"));
+        assert!(!emoji_heavy("🔥🔥🔥🔥🔥"));
+        assert!(!emoji_heavy("🎉🎉🎉🎉🎉🎉 congratulations to everyone on the release"));
+        assert!(!emoji_heavy("plain text"));
+        // Variation selectors and joiners don't dilute the density.
+        assert!(emoji_heavy("❤\u{fe0f}❤\u{fe0f}❤\u{fe0f}❤\u{fe0f}❤\u{fe0f}❤\u{fe0f}"));
+        assert_eq!(signals(&post("💎💎💎💎💎💎💎")), vec![Signal::Emoji]);
+    }
+
+    #[test]
+    fn repeated_text() {
+        let earlier = vec![normalize("Earn  money FAST\nfrom home")];
+        let p = Post { earlier: &earlier, ..post("earn money fast from home") };
+        assert_eq!(score(&p).hits, vec![(Signal::Repeated, 20)]);
+        assert!(!repeated("something else entirely", &earlier));
+        // Short replies such as "ok" or "thanks" repeat naturally.
+        let short = vec![normalize("thanks")];
+        assert!(!repeated("Thanks", &short));
+    }
+
+    #[test]
+    fn signals_combine() {
+        let urls = vec!["https://t.me/+abc".to_string()];
+        let p = Post { since_join: 20, links: &urls, channel_forward: true, name: "Crypto Queen", ..post("join") };
+        let s = score(&p);
+        assert_eq!(s.total, 25 + 15 + 30 + 30 + 15);
+        assert_eq!(s.describe(),
+            "posted right after joining +25, links +15, invite link +30, forwarded from a channel +30, suspicious name +15");
+    }
+
+    #[test]
+    fn thresholds_map_to_verdicts() {
+        let p = SpamPolicy { enabled: true, delete: 40, mute: 60, ban: 90 };
+        assert_eq!(verdict(0, &p), None);
+        assert_eq!(verdict(39, &p), None);
+        assert_eq!(verdict(40, &p), Some(Verdict::Delete));
+        assert_eq!(verdict(59, &p), Some(Verdict::Delete));
+        assert_eq!(verdict(60, &p), Some(Verdict::Mute));
+        assert_eq!(verdict(89, &p), Some(Verdict::Mute));
+        assert_eq!(verdict(90, &p), Some(Verdict::Ban));
+        assert_eq!(verdict(500, &p), Some(Verdict::Ban));
+    }
+
+    #[test]
+    fn zero_switches_a_step_off() {
+        let no_mute = SpamPolicy { enabled: true, delete: 40, mute: 0, ban: 90 };
+        assert_eq!(verdict(0, &no_mute), None);
+        assert_eq!(verdict(75, &no_mute), Some(Verdict::Delete));
+        assert_eq!(verdict(90, &no_mute), Some(Verdict::Ban));
+        let ban_only = SpamPolicy { enabled: true, delete: 0, mute: 0, ban: 50 };
+        assert_eq!(verdict(49, &ban_only), None);
+        assert_eq!(verdict(50, &ban_only), Some(Verdict::Ban));
+        let off = SpamPolicy { enabled: true, delete: 0, mute: 0, ban: 0 };
+        assert_eq!(verdict(1000, &off), None);
+    }
+}
@@ -0,0 +1,184 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — JSON-lines Journal Storage
+//  Every write is appended to the journal as one JSON line. On
+//  startup the journal is replayed, migrated to SCHEMA_VERSION and
+//  compacted into a fresh file holding one line per live key.
+//
+//  File layout:
+//    {"schema":2}
+//    {"op":"put","t":"notes","k":"-100123:rules","v":"Be nice"}
+//    {"op":"del","t":"notes","k":"-100123:rules"}
+// ════════════════════════════════════════════════════════════════
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::memory::{MemoryStorage, Tables};
+use super::{Storage, SCHEMA_VERSION};
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    schema: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Put { t: String, k: String, v: Value },
+    Del { t: String, k: String },
+}
+
+pub struct JsonFileStorage {
+    mem: MemoryStorage,
+    journal: Mutex<File>,
+}
+
+impl JsonFileStorage {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let path = Path::new(path);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+
+        let (version, mut tables) = if path.exists() {
+            replay(path)?
+        } else {
+            (SCHEMA_VERSION, Tables::new())
+        };
+        migrate(version, &mut tables)?;
+        compact(path, &tables)?;
+
+        let journal = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+
+        let keys: usize = tables.values().map(|m| m.len()).sum();
+        log::info!("💾 Storage: {} ({} keys, schema v{})", path.display(), keys, SCHEMA_VERSION);
+
+        Ok(JsonFileStorage { mem: MemoryStorage::from_tables(tables), journal: Mutex::new(journal) })
+    }
+
+    fn append(&self, file: &mut File, record: &Record) {
+        let line = match serde_json::to_string(record) {
+            Ok(l) => l,
+            Err(e) => { log::error!("storage: encode failed: {}", e); return; }
+        };
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            log::error!("storage: journal write failed: {}", e);
+        }
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn get(&self, table: &str, key: &str) -> Option<Value> {
+        self.mem.get(table, key)
+    }
+
+    fn put(&self, table: &str, key: &str, value: Value) {
+        let mut file = self.journal.lock().unwrap();
+        self.append(&mut file, &Record::Put { t: table.into(), k: key.into(), v: value.clone() });
+        self.mem.put(table, key, value);
+    }
+
+    fn delete(&self, table: &str, key: &str) -> bool {
+        let mut file = self.journal.lock().unwrap();
+        let existed = self.mem.delete(table, key);
+        if existed {
+            self.append(&mut file, &Record::Del { t: table.into(), k: key.into() });
+        }
+        existed
+    }
+
+    fn scan(&self, table: &str, prefix: &str) -> Vec<(String, Value)> {
+        self.mem.scan(table, prefix)
+    }
+
+    fn update(&self, table: &str, key: &str, f: &mut dyn FnMut(Option<Value>) -> Option<Value>) -> Option<Value> {
+        let mut file = self.journal.lock().unwrap();
+        let new = self.mem.update(table, key, f);
+        let record = match &new {
+            Some(v) => Record::Put { t: table.into(), k: key.into(), v: v.clone() },
+            None    => Record::Del { t: table.into(), k: key.into() },
+        };
+        self.append(&mut file, &record);
+        new
+    }
+}
+
+// ── Startup: replay, migrate, compact ────────────────────────────────────────
+
+fn replay(path: &Path) -> anyhow::Result<(u32, Tables)> {
+    let file = File::open(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let version = match lines.next() {
+        Some(first) => serde_json::from_str::<Header>(&first?)
+            .with_context(|| format!("{} has no schema header", path.display()))?
+            .schema,
+        None => SCHEMA_VERSION,
+    };
+    if version > SCHEMA_VERSION {
+        bail!("{} uses schema v{}, this build only knows v{}", path.display(), version, SCHEMA_VERSION);
+    }
+
+    let mut tables = Tables::new();
+    for (n, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() { continue; }
+        match serde_json::from_str::<Record>(&line) {
+            Ok(Record::Put { t, k, v }) => { tables.entry(t).or_default().insert(k, v); }
+            Ok(Record::Del { t, k })    => { if let Some(m) = tables.get_mut(&t) { m.remove(&k); } }
+            // A torn last line after a crash is expected; anything else is worth a look.
+            Err(e) => log::warn!("storage: skipping journal line {}: {}", n + 2, e),
+        }
+    }
+    Ok((version, tables))
+}
+
+/// Upgrades tables written by an older schema, one step per version,
+/// each rewriting `tables` in place.
+fn migrate(from: u32, tables: &mut Tables) -> anyhow::Result<()> {
+    if from < 1 {
+        bail!("unknown storage schema v{}", from);
+    }
+    if from < 2 {
+        // v2: warns are a list of {reason, by, at} instead of a bare count.
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        if let Some(warns) = tables.get_mut(super::WARNS) {
+            for v in warns.values_mut() {
+                if let Some(n) = v.as_u64() {
+                    let entry = serde_json::json!({ "reason": "", "by": 0, "at": now });
+                    *v = Value::Array(vec![entry; n as usize]);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compact(path: &Path, tables: &Tables) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    {
+        let mut out = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+        writeln!(out, "{}", serde_json::to_string(&Header { schema: SCHEMA_VERSION })?)?;
+        for (t, map) in tables {
+            for (k, v) in map {
+                let rec = Record::Put { t: t.clone(), k: k.clone(), v: v.clone() };
+                writeln!(out, "{}", serde_json::to_string(&rec)?)?;
+            }
+        }
+        out.sync_all()?;
+    }
+    fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
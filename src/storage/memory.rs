@@ -0,0 +1,71 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — In-memory Storage
+//  Resets on bot restart. Also the working set of the JSON backend.
+// ════════════════════════════════════════════════════════════════
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use super::Storage;
+
+pub type Tables = HashMap<String, BTreeMap<String, Value>>;
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_tables(tables: Tables) -> Self {
+        MemoryStorage { tables: Mutex::new(tables) }
+    }
+
+    /// Runs `f` with the tables locked (used by the JSON backend to keep the
+    /// journal write and the in-memory update atomic).
+    pub fn with_tables<R>(&self, f: impl FnOnce(&mut Tables) -> R) -> R {
+        f(&mut self.tables.lock().unwrap())
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, table: &str, key: &str) -> Option<Value> {
+        self.with_tables(|t| t.get(table).and_then(|m| m.get(key)).cloned())
+    }
+
+    fn put(&self, table: &str, key: &str, value: Value) {
+        self.with_tables(|t| {
+            t.entry(table.to_string()).or_default().insert(key.to_string(), value);
+        });
+    }
+
+    fn delete(&self, table: &str, key: &str) -> bool {
+        self.with_tables(|t| t.get_mut(table).and_then(|m| m.remove(key)).is_some())
+    }
+
+    fn scan(&self, table: &str, prefix: &str) -> Vec<(String, Value)> {
+        self.with_tables(|t| match t.get(table) {
+            Some(m) => m
+                .range(prefix.to_string()..)
+                .take_while(|(k, _)| k.starts_with(prefix))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            None => vec![],
+        })
+    }
+
+    fn update(&self, table: &str, key: &str, f: &mut dyn FnMut(Option<Value>) -> Option<Value>) -> Option<Value> {
+        self.with_tables(|t| {
+            let map = t.entry(table.to_string()).or_default();
+            match f(map.remove(key)) {
+                Some(v) => { map.insert(key.to_string(), v.clone()); Some(v) }
+                None => None,
+            }
+        })
+    }
+}
@@ -0,0 +1,114 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Storage
+//  A small table/key/JSON-value store behind the `Storage` trait.
+//  Backends:
+//    memory  — lost on restart (handy for development)
+//    json    — append-only JSON-lines journal, replayed on startup
+//  Selected with STORAGE_BACKEND / STORAGE_PATH in .env.
+// ════════════════════════════════════════════════════════════════
+
+pub mod json_file;
+pub mod memory;
+
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::config::{StorageBackend, StorageConfig};
+
+/// Bump when the layout of any table changes, and add a step to
+/// `json_file::migrate` that upgrades older journals.
+pub const SCHEMA_VERSION: u32 = 2;
+
+// ── Table names ──────────────────────────────────────────────────────────────
+
+pub const WARNS:          &str = "warns";
+pub const FILTERS:        &str = "filters";
+pub const NOTES:          &str = "notes";
+pub const SUDO:           &str = "sudo";
+pub const CHATS:          &str = "chats";
+pub const USERS:          &str = "users";
+pub const USERNAMES:      &str = "usernames";
+pub const SETTINGS:       &str = "settings";
+pub const AUDIT:          &str = "audit";
+pub const SCHEDULE:       &str = "schedule";
+pub const CAPTCHAS:       &str = "captchas";
+pub const JOIN_REQUESTS:  &str = "join_requests";
+pub const QUESTIONNAIRES: &str = "questionnaires";
+pub const BLOCKLIST:      &str = "blocklist";
+pub const PAUSED_JOINS:   &str = "paused_joins";
+pub const REPORTS:        &str = "reports";
+pub const REPORT_PMS:     &str = "report_pms";
+pub const APPROVED:       &str = "approved";
+pub const GBANS:          &str = "gbans";
+pub const ANON_COMMANDS:  &str = "anon_commands";
+pub const NEWCOMERS:      &str = "newcomers";
+
+pub trait Storage: Send + Sync {
+    fn get(&self, table: &str, key: &str) -> Option<Value>;
+    fn put(&self, table: &str, key: &str, value: Value);
+    /// Returns true if the key existed.
+    fn delete(&self, table: &str, key: &str) -> bool;
+    /// All entries in `table` whose key starts with `prefix`, ordered by key.
+    fn scan(&self, table: &str, prefix: &str) -> Vec<(String, Value)>;
+    /// Atomically replaces the value at `key` with `f(old)`; `None` deletes
+    /// it. Returns the new value.
+    fn update(&self, table: &str, key: &str, f: &mut dyn FnMut(Option<Value>) -> Option<Value>) -> Option<Value>;
+}
+
+pub type Db = Arc<dyn Storage>;
+
+// ── Typed helpers ────────────────────────────────────────────────────────────
+
+impl dyn Storage {
+    pub fn load<T: DeserializeOwned>(&self, table: &str, key: &str) -> Option<T> {
+        self.get(table, key).and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    pub fn save<T: Serialize>(&self, table: &str, key: &str, value: &T) {
+        match serde_json::to_value(value) {
+            Ok(v)  => self.put(table, key, v),
+            Err(e) => log::error!("storage: could not serialize {}/{}: {}", table, key, e),
+        }
+    }
+
+    /// Typed read-modify-write; see `Storage::update`.
+    pub fn modify<T, F>(&self, table: &str, key: &str, f: F) -> Option<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<T>) -> Option<T>,
+    {
+        let mut f = Some(f);
+        let new = self.update(table, key, &mut |old| {
+            let old = old.and_then(|v| serde_json::from_value(v).ok());
+            let f = f.take().expect("update callback runs once");
+            f(old).and_then(|t| serde_json::to_value(t).ok())
+        });
+        new.and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    pub fn load_all<T: DeserializeOwned>(&self, table: &str, prefix: &str) -> Vec<(String, T)> {
+        self.scan(table, prefix)
+            .into_iter()
+            .filter_map(|(k, v)| serde_json::from_value(v).ok().map(|t| (k, t)))
+            .collect()
+    }
+}
+
+/// Key for per-chat entries: `"<chat_id>:<rest>"`.
+pub fn chat_key(chat_id: i64, rest: impl std::fmt::Display) -> String {
+    format!("{}:{}", chat_id, rest)
+}
+
+/// Prefix matching every key written with `chat_key(chat_id, …)`.
+pub fn chat_prefix(chat_id: i64) -> String {
+    format!("{}:", chat_id)
+}
+
+pub fn open(cfg: &StorageConfig) -> anyhow::Result<Db> {
+    match cfg.backend {
+        StorageBackend::Memory => Ok(Arc::new(memory::MemoryStorage::new())),
+        StorageBackend::Json   => Ok(Arc::new(json_file::JsonFileStorage::open(&cfg.path)?)),
+    }
+}
@@ -0,0 +1,191 @@
+// ════════════════════════════════════════════════════════════════
+//  Rustace Bot — Target Resolver
+//  Works out who a moderation command is aimed at:
+//    reply         /ban 1d spam         (replied-to user)
+//    numeric ID    /ban 123456789 spam
+//    @username     /ban @someone spam   (from the local user directory)
+//    text mention  /ban John spam       (mention of a user without username)
+//  Replies to a post made as a chat (a channel, or the group itself for
+//  anonymous admins) and negative IDs resolve to that chat instead.
+//  Whatever follows the target is returned as `rest` (duration, reason…).
+// ════════════════════════════════════════════════════════════════
+
+use serde::{Deserialize, Serialize};
+use tgbotrs::{
+    gen_methods::SendMessageParams,
+    types::{Message, User},
+    Bot,
+};
+
+use crate::html;
+use crate::storage::{Db, USERNAMES, USERS};
+
+// ── User directory ───────────────────────────────────────────────────────────
+//   Bots can't look users up by @username, so every user the bot sees is
+//   recorded here: `users` maps id → profile, `usernames` maps the
+//   lower-cased username → id.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownUser {
+    pub first_name: String,
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+/// Records `user`; only writes when the name or username changed.
+pub fn remember(db: &Db, user: &User) {
+    let entry = KnownUser { first_name: user.first_name.clone(), username: user.username.clone() };
+    let key = user.id.to_string();
+    let old: Option<KnownUser> = db.load(USERS, &key);
+    if old.as_ref() == Some(&entry) { return; }
+
+    if let Some(prev) = old.and_then(|o| o.username) {
+        if entry.username.as_deref() != Some(prev.as_str()) {
+            db.delete(USERNAMES, &prev.to_lowercase());
+        }
+    }
+    if let Some(u) = &entry.username {
+        db.save(USERNAMES, &u.to_lowercase(), &user.id);
+    }
+    db.save(USERS, &key, &entry);
+}
+
+pub fn known(db: &Db, user_id: i64) -> Option<KnownUser> {
+    db.load(USERS, &user_id.to_string())
+}
+
+pub fn by_username(db: &Db, username: &str) -> Option<i64> {
+    db.load(USERNAMES, &username.trim_start_matches('@').to_lowercase())
+}
+
+// ── Resolution ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Lookup {
+    Found(Target),
+    /// A chat posting as itself; only /ban and /unban act on those.
+    SenderChat(Target),
+    /// An @username the directory has never seen.
+    Unknown(String),
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub target: Lookup,
+    /// Text after the target, trimmed.
+    pub rest: String,
+}
+
+/// Resolves the target of the command in `msg` (text or caption).
+pub fn resolve(msg: &Message, db: &Db) -> Resolved {
+    let (text, entities) = match (&msg.text, &msg.caption) {
+        (Some(t), _)    => (t.as_str(), msg.entities.as_deref().unwrap_or(&[])),
+        (None, Some(c)) => (c.as_str(), msg.caption_entities.as_deref().unwrap_or(&[])),
+        _ => return Resolved { target: Lookup::Missing, rest: String::new() },
+    };
+
+    // Skip the command word, then any whitespace.
+    let after_cmd = text.find(char::is_whitespace).unwrap_or(text.len());
+    let start = text[after_cmd..]
+        .find(|c: char| !c.is_whitespace())
+        .map(|i| after_cmd + i)
+        .unwrap_or(text.len());
+    let args = &text[start..];
+
+    // In forum topics every message "replies" to the topic's creation
+    // message, so that one doesn't count as a target.
+    let reply = msg.reply_to_message.as_ref()
+        .filter(|r| !(msg.is_topic_message == Some(true) && r.forum_topic_created.is_some()));
+    if let Some(r) = reply {
+        // Posts made as a chat carry a placeholder bot in `from`.
+        if let Some(c) = &r.sender_chat {
+            let name = c.title.clone().unwrap_or_else(|| c.id.to_string());
+            return Resolved { target: Lookup::SenderChat(Target { id: c.id, name }), rest: args.trim().to_string() };
+        }
+        if let Some(user) = &r.from {
+            return found(user.id, user.first_name.clone(), args);
+        }
+    }
+
+    // text_mention starting exactly at the first argument.
+    let start16 = utf16_len(&text[..start]);
+    let mention = entities.iter().find(|e| e.r#type == "text_mention" && e.offset == start16);
+    if let Some((e, user)) = mention.and_then(|e| e.user.as_ref().map(|u| (e, u))) {
+        let end = byte_index(text, e.offset + e.length);
+        return found(user.id, user.first_name.clone(), &text[end..]);
+    }
+
+    let word_end = args.find(char::is_whitespace).unwrap_or(args.len());
+    let (word, tail) = args.split_at(word_end);
+
+    if let Ok(id) = word.parse::<i64>() {
+        // Chat IDs are negative.
+        if id < 0 {
+            return Resolved { target: Lookup::SenderChat(Target { id, name: id.to_string() }), rest: tail.trim().to_string() };
+        }
+        let name = known(db, id).map(|k| k.first_name).unwrap_or_else(|| id.to_string());
+        return found(id, name, tail);
+    }
+    if word.len() > 1 && word.starts_with('@') {
+        return match by_username(db, word) {
+            Some(id) => {
+                let name = known(db, id).map(|k| k.first_name).unwrap_or_else(|| word.to_string());
+                found(id, name, tail)
+            }
+            None => Resolved { target: Lookup::Unknown(word.to_string()), rest: tail.trim().to_string() },
+        };
+    }
+
+    Resolved { target: Lookup::Missing, rest: args.trim().to_string() }
+}
+
+fn found(id: i64, name: String, rest: &str) -> Resolved {
+    Resolved { target: Lookup::Found(Target { id, name }), rest: rest.trim().to_string() }
+}
+
+// Entity offsets are in UTF-16 code units.
+fn utf16_len(s: &str) -> i64 {
+    s.encode_utf16().count() as i64
+}
+
+fn byte_index(s: &str, utf16_pos: i64) -> usize {
+    let mut units = 0i64;
+    for (i, c) in s.char_indices() {
+        if units >= utf16_pos { return i; }
+        units += c.len_utf16() as i64;
+    }
+    s.len()
+}
+
+// ════════════════════════════════════════════════════════════════
+//  Handler helper — the target, or a usage / "unknown user" reply.
+//  `usage` is the argument hint after the target, e.g. "[duration] [reason]".
+// ════════════════════════════════════════════════════════════════
+
+pub async fn require<'a>(bot: &Bot, chat_id: i64, resolved: &'a Resolved, command: &str, usage: &str) -> Option<&'a Target> {
+    let text = match &resolved.target {
+        Lookup::Found(t) => return Some(t),
+        Lookup::SenderChat(t) if t.id == chat_id =>
+            "🕶 That was posted by an anonymous admin, who can't be targeted.".to_string(),
+        Lookup::SenderChat(t) => format!(
+            "📢 <b>{}</b> posts as a chat, not a user.\n\n<i>Only /ban and /unban work on channels.</i>",
+            html::escape(&t.name)),
+        Lookup::Unknown(name) => format!(
+            "❓ I haven't seen <b>{}</b> yet.\n\n<i>Reply to one of their messages or use their numeric ID.</i>",
+            html::escape(name)),
+        Lookup::Missing => format!(
+            "⚠️ <b>Usage:</b> <code>{} &lt;user&gt; {}</code>\n\n\
+            <i>user = reply to their message, numeric ID, @username or a mention</i>",
+            command, html::escape(usage)).replace(" </code>", "</code>"),
+    };
+    let p = SendMessageParams::new().parse_mode("HTML");
+    let _ = bot.send_message(chat_id, text, Some(p)).await;
+    None
+}